@@ -2,26 +2,164 @@
 
 use ink_lang as ink;
 
+// The generated dispatch code binds the result of messages returning `()`.
+#[allow(clippy::let_unit_value)]
 #[ink::contract]
-mod erc20 {
+pub mod erc20 {
+    use ink_lang as ink;
+    use ink_prelude::{
+        string::String,
+        vec::Vec,
+    };
+    #[cfg(not(test))]
+    use ink_env::call::{
+        build_call,
+        Call,
+        ExecutionInput,
+        Selector,
+    };
+    use ink_env::hash::Blake2x256;
     use ink_storage::{
-        collections::HashMap,
-        lazy::Lazy,
+        traits::{
+            PackedLayout,
+            SpreadAllocate,
+            SpreadLayout,
+        },
+        Mapping,
     };
 
     #[ink(storage)]
+    #[derive(SpreadAllocate)]
     pub struct Erc20 {
-        // 单值用lazy
-        total_supply: Lazy<Balance>,
-        balances: HashMap<AccountId, Balance>,
-        allowances: HashMap<(AccountId, AccountId), Balance>
+        total_supply: Balance,
+        balances: Mapping<AccountId, Balance>,
+        allowances: Mapping<(AccountId, AccountId), Balance>,
+        name: Option<String>,
+        symbol: Option<String>,
+        decimals: u8,
+        owner: AccountId,
+        paused: bool,
+        cap: Option<Balance>,
+        check_zero_address: bool,
+        nonces: Mapping<AccountId, u64>,
+        current_snapshot_id: u64,
+        account_snapshots: Mapping<(AccountId, u32), (u64, Balance)>,
+        account_snapshot_count: Mapping<AccountId, u32>,
+        total_supply_snapshots: Mapping<u32, (u64, Balance)>,
+        total_supply_snapshot_count: u32,
+        delegates: Mapping<AccountId, AccountId>,
+        vote_checkpoints: Mapping<(AccountId, u32), (u32, Balance)>,
+        vote_checkpoint_count: Mapping<AccountId, u32>,
+        flash_fee_bps: u16,
+        fee_bps: u16,
+        treasury: Option<AccountId>,
+        frozen: Mapping<AccountId, bool>,
+        roles: Mapping<(RoleId, AccountId), bool>,
+        pending_owner: Option<AccountId>,
+        allowance_expiry: Mapping<(AccountId, AccountId), u64>,
+        holder_count: u32,
+        holders: Mapping<u32, AccountId>,
+        holder_index: Mapping<AccountId, u32>,
+        mint_burn_events: bool,
+        skip_zero_address_transfers: bool,
+        large_transfer_threshold: Option<Balance>,
+        transfer_delay: u64,
+        next_queued_id: u64,
+        queued_transfers: Mapping<u64, QueuedTransfer>,
+        vestings: Mapping<AccountId, Vesting>,
+        reward_rate: Balance,
+        reward_per_token: u128,
+        last_reward_block: u32,
+        staked: Mapping<AccountId, (Balance, u128)>,
+        accrued_rewards: Mapping<AccountId, Balance>,
+        entered: Mapping<(), bool>,
+        total_minted: Balance,
+        total_burned: Balance,
+        excluded: Vec<AccountId>,
+        emission_per_block: Balance,
+        last_emission_block: u32,
+        emission_recipient: AccountId,
+        max_tx_amount: Option<Balance>,
+        transfer_cooldown: u64,
+        last_transfer: Mapping<AccountId, u64>,
+        allowance_pairs: Mapping<u32, (AccountId, AccountId)>,
+        allowance_pair_index: Mapping<(AccountId, AccountId), u32>,
+        allowance_pair_count: u32,
+        burn_bps: u16,
+        admins: Vec<AccountId>,
+        threshold: u8,
+        proposed_actions: Mapping<[u8; 32], AdminAction>,
+        confirmations: Mapping<[u8; 32], Vec<AccountId>>,
+        max_wallet_amount: Option<Balance>,
+        dividend_per_share: Balance,
+        last_claim_point: Mapping<AccountId, Balance>,
+        unclaimed_dividends: Mapping<AccountId, Balance>,
+        withdrawn_dividends: Mapping<AccountId, Balance>,
+        balance_weights: Mapping<(AccountId, u32), (u64, u128, Balance)>,
+        balance_weight_count: Mapping<AccountId, u32>,
+        next_proposal_id: u64,
+        proposals: Mapping<u64, Proposal>,
+        proposal_votes: Mapping<(u64, AccountId), bool>,
+        periodic_allowances: Mapping<(AccountId, AccountId), (Balance, u64, Balance, u64)>,
+        rounding_mode: RoundingMode,
+        used_source_nonces: Mapping<u64, bool>,
+        approval_history: Mapping<AccountId, Vec<(AccountId, Balance, u64)>>,
+        price_per_token: Balance,
+        sale_active: bool,
+        emit_failures: bool,
+        auto_self_delegate: bool,
+        migration_complete: bool,
+        trading_enabled: bool,
+        transfer_whitelist: Mapping<AccountId, bool>,
+        pending_withdrawals: Mapping<AccountId, Balance>,
+        metadata_frozen: bool,
+        min_supply: Option<Balance>,
+        oracle: Option<AccountId>,
+        mint_cap_per_block: Option<Balance>,
+        minted_this_block: (u32, Balance),
+        used_transfer_nonces: Mapping<(AccountId, u64), bool>,
+        index_values: bool,
+        spenders: Mapping<(AccountId, u32), AccountId>,
+        spender_index: Mapping<(AccountId, AccountId), u32>,
+        spender_count: Mapping<AccountId, u32>,
+        launch_block: Option<u32>,
+        transfers_started: bool,
+        wrapped_native: Balance,
+        native_deposits: Mapping<AccountId, Balance>
     }
 
     #[ink(event)]
     pub struct Transfer {
         #[ink(topic)]
         from: Option<AccountId>,
+        #[ink(topic)]
+        to: Option<AccountId>,
+        value: Balance
+    }
+
+    /// Emitted next to `Transfer` by contracts built with
+    /// `new_with_indexed_values`, so that indexers can filter on the value.
+    #[ink(event)]
+    pub struct TransferIndexed {
+        #[ink(topic)]
+        from: Option<AccountId>,
+        #[ink(topic)]
         to: Option<AccountId>,
+        #[ink(topic)]
+        value: Balance
+    }
+
+    #[ink(event)]
+    pub struct Mint {
+        #[ink(topic)]
+        to: AccountId,
+        value: Balance
+    }
+
+    #[ink(event)]
+    pub struct Burn {
+        #[ink(topic)]
+        from: AccountId,
         value: Balance
     }
 
@@ -35,6 +173,15 @@ mod erc20 {
         value: Balance
     }
 
+    #[ink(event)]
+    pub struct TransferWithData {
+        #[ink(topic)]
+        from: Option<AccountId>,
+        to: Option<AccountId>,
+        value: Balance,
+        data: Vec<u8>
+    }
+
     #[ink(event)]
     pub struct Approval {
         #[ink(topic)]
@@ -44,92 +191,776 @@ mod erc20 {
         value: Balance
     }
 
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        previous: AccountId,
+        #[ink(topic)]
+        new: AccountId
+    }
+
+    #[ink(event)]
+    pub struct OwnershipTransferStarted {
+        #[ink(topic)]
+        previous: AccountId,
+        #[ink(topic)]
+        new: AccountId
+    }
+
+    #[ink(event)]
+    pub struct Paused {
+        account: AccountId
+    }
+
+    #[ink(event)]
+    pub struct Unpaused {
+        account: AccountId
+    }
+
+    #[ink(event)]
+    pub struct Snapshot {
+        id: u64
+    }
+
+    #[ink(event)]
+    pub struct DelegateChanged {
+        #[ink(topic)]
+        delegator: AccountId,
+        #[ink(topic)]
+        from_delegate: Option<AccountId>,
+        #[ink(topic)]
+        to_delegate: AccountId
+    }
+
+    #[ink(event)]
+    pub struct DelegateVotesChanged {
+        #[ink(topic)]
+        delegate: AccountId,
+        previous_votes: Balance,
+        new_votes: Balance
+    }
+
+    #[ink(event)]
+    pub struct AccountFrozen {
+        #[ink(topic)]
+        account: AccountId
+    }
+
+    #[ink(event)]
+    pub struct AccountUnfrozen {
+        #[ink(topic)]
+        account: AccountId
+    }
+
+    #[ink(event)]
+    pub struct RoleGranted {
+        #[ink(topic)]
+        role: RoleId,
+        #[ink(topic)]
+        account: AccountId,
+        sender: AccountId
+    }
+
+    #[ink(event)]
+    pub struct RoleRevoked {
+        #[ink(topic)]
+        role: RoleId,
+        #[ink(topic)]
+        account: AccountId,
+        sender: AccountId
+    }
+
+    #[ink(event)]
+    pub struct ForcedTransfer {
+        #[ink(topic)]
+        by: AccountId,
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        value: Balance
+    }
+
+    #[ink(event)]
+    pub struct TransferQueued {
+        #[ink(topic)]
+        id: u64,
+        #[ink(topic)]
+        from: AccountId,
+        to: AccountId,
+        value: Balance,
+        ready_at: u64
+    }
+
+    #[ink(event)]
+    pub struct TransferExecuted {
+        #[ink(topic)]
+        id: u64
+    }
+
+    #[ink(event)]
+    pub struct VestingCreated {
+        #[ink(topic)]
+        beneficiary: AccountId,
+        total: Balance,
+        start: u64,
+        duration: u64
+    }
+
+    #[ink(event)]
+    pub struct TokensReleased {
+        #[ink(topic)]
+        beneficiary: AccountId,
+        value: Balance
+    }
+
+    #[ink(event)]
+    pub struct Staked {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance
+    }
+
+    #[ink(event)]
+    pub struct Unstaked {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance
+    }
+
+    #[ink(event)]
+    pub struct RewardPaid {
+        #[ink(topic)]
+        account: AccountId,
+        reward: Balance
+    }
+
+    #[ink(event)]
+    pub struct CodeUpgraded {
+        code_hash: [u8; 32]
+    }
+
+    #[ink(event)]
+    pub struct Funded {
+        #[ink(topic)]
+        from: AccountId,
+        amount: Balance
+    }
+
+    #[ink(event)]
+    pub struct Proposed {
+        #[ink(topic)]
+        action_hash: [u8; 32],
+        #[ink(topic)]
+        proposer: AccountId
+    }
+
+    #[ink(event)]
+    pub struct Confirmed {
+        #[ink(topic)]
+        action_hash: [u8; 32],
+        #[ink(topic)]
+        admin: AccountId
+    }
+
+    #[ink(event)]
+    pub struct Executed {
+        #[ink(topic)]
+        action_hash: [u8; 32]
+    }
+
+    #[ink(event)]
+    pub struct DividendDistributed {
+        #[ink(topic)]
+        from: AccountId,
+        amount: Balance
+    }
+
+    #[ink(event)]
+    pub struct DividendClaimed {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance
+    }
+
+    #[ink(event)]
+    pub struct ProposalCreated {
+        #[ink(topic)]
+        proposal_id: u64,
+        #[ink(topic)]
+        proposer: AccountId,
+        description_hash: [u8; 32],
+        end_block: u32
+    }
+
+    #[ink(event)]
+    pub struct VoteCast {
+        #[ink(topic)]
+        proposal_id: u64,
+        #[ink(topic)]
+        voter: AccountId,
+        support: bool,
+        weight: Balance
+    }
+
+    #[ink(event)]
+    pub struct ProposalExecuted {
+        #[ink(topic)]
+        proposal_id: u64,
+        call_data: Vec<u8>
+    }
+
+    #[ink(event)]
+    pub struct Locked {
+        #[ink(topic)]
+        from: AccountId,
+        amount: Balance,
+        dest_chain: u32,
+        dest_account: Vec<u8>
+    }
+
+    #[ink(event)]
+    pub struct Unlocked {
+        #[ink(topic)]
+        to: AccountId,
+        amount: Balance,
+        source_nonce: u64
+    }
+
+    #[ink(event)]
+    pub struct TransferFailed {
+        #[ink(topic)]
+        from: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        value: Balance,
+        reason: u8
+    }
+
+    #[ink(event)]
+    pub struct WithdrawalEscrowed {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance
+    }
+
+    #[ink(event)]
+    pub struct WithdrawalClaimed {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance
+    }
+
+    /// Emitted by `emergency_withdraw`; `token` is `None` for native currency.
+    #[ink(event)]
+    pub struct EmergencyWithdrawal {
+        #[ink(topic)]
+        token: Option<AccountId>,
+        #[ink(topic)]
+        to: AccountId,
+        amount: Balance
+    }
+
     #[derive(Debug, Eq, PartialEq, scale::Encode, scale::Decode)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
     pub enum Error {
         InsufficientBalance,
-        InsufficientApproval
+        InsufficientApproval,
+        /// Returned if an arithmetic operation on a balance would overflow.
+        Overflow,
+        /// Returned if the caller is not the contract owner.
+        NotOwner,
+        /// Returned if the contract is paused.
+        Paused,
+        /// Returned if minting would push the total supply above the cap.
+        CapExceeded,
+        /// Returned if a batch holds more than `MAX_BATCH_LEN` entries.
+        BatchTooLarge,
+        /// Returned if the zero account is used as recipient or spender.
+        ZeroAddress,
+        /// Returned if a permit is used after its deadline.
+        PermitExpired,
+        /// Returned if a permit signature does not belong to the owner.
+        InvalidSignature,
+        /// Returned if a receiving contract trapped or rejected a callback.
+        TransferCallbackFailed,
+        /// Returned if a flash loan borrower cannot pay back the loan plus fee.
+        FlashLoanNotRepaid,
+        /// Returned if a basis point value is larger than 10 000.
+        InvalidBasisPoints,
+        /// Returned if a frozen account sends, receives or approves tokens.
+        AccountFrozen,
+        /// Returned if the caller lacks the role a message requires.
+        MissingRole,
+        /// Returned if an account other than the pending owner accepts ownership.
+        NotPendingOwner,
+        /// Returned if sending native tokens out of the contract fails.
+        NativeTransferFailed,
+        /// Returned if `recover_tokens` targets this contract's own token.
+        CannotRecoverSelf,
+        /// Returned if a foreign token trapped or rejected a recovery transfer.
+        RecoveryFailed,
+        /// Returned if an allowance no longer holds the value the caller expected.
+        AllowanceChanged,
+        /// Returned if a transfer above the large transfer threshold was not queued.
+        TimelockRequired,
+        /// Returned if a queued transfer is executed before it is ready.
+        TimelockNotElapsed,
+        /// Returned if no queued transfer exists under the given id.
+        UnknownQueuedTransfer,
+        /// Returned if the beneficiary already has a vesting schedule.
+        VestingExists,
+        /// Returned if an account unstakes more than it staked.
+        InsufficientStake,
+        /// Returned if a message making an external call is re-entered.
+        ReentrantCall,
+        /// Returned if replacing the contract code fails.
+        UpgradeFailed,
+        /// Returned if a single transfer moves more than the maximum transaction amount.
+        MaxTxExceeded,
+        /// Returned if an account transfers again before its cooldown has passed.
+        CooldownActive,
+        /// Returned if a query would return or sort more than `MAX_EXPORT_ENTRIES` entries.
+        TooManyEntries,
+        /// Returned if the caller is not one of the multisig admins.
+        NotAdmin,
+        /// Returned if an action gated by the multisig is called directly.
+        MultisigRequired,
+        /// Returned if a multisig threshold is zero or above the number of admins.
+        InvalidThreshold,
+        /// Returned if an identical action is already waiting for confirmations.
+        ActionAlreadyProposed,
+        /// Returned if no pending action matches the given hash.
+        UnknownAction,
+        /// Returned if an admin confirms the same action twice.
+        AlreadyConfirmed,
+        /// Returned if a transfer would push the recipient's balance above `max_wallet_amount`.
+        MaxWalletExceeded,
+        /// Returned if dividends are distributed while no tokens exist.
+        NoSupply,
+        /// Returned if no proposal has the given id.
+        UnknownProposal,
+        /// Returned if an account votes twice on the same proposal.
+        AlreadyVoted,
+        /// Returned if the caller had no votes when the proposal was created.
+        NoVotingPower,
+        /// Returned if a vote is cast after the voting period ended.
+        VotingClosed,
+        /// Returned if a proposal is executed before its voting period ended.
+        VotingActive,
+        /// Returned if too few votes were cast on a proposal to execute it.
+        QuorumNotReached,
+        /// Returned if a proposal did not get more votes for than against.
+        ProposalDefeated,
+        /// Returned if a proposal is executed a second time.
+        ProposalAlreadyExecuted,
+        /// Returned if a spender pulls more than its periodic allowance leaves in the current period.
+        PeriodLimitExceeded,
+        /// Returned if the contract is terminated while tokens are still in circulation.
+        SupplyOutstanding,
+        /// Returned if a bridge unlock reuses a source nonce.
+        NonceAlreadyUsed,
+        /// Returned if tokens are bought while the sale is off or has no price.
+        SaleInactive,
+        /// Returned if `migrate_balances` is called after the migration was finished.
+        MigrationComplete,
+        /// Returned if neither side of a transfer is whitelisted before trading is enabled.
+        TradingNotEnabled,
+        /// Returned if the token metadata is changed after it was frozen.
+        MetadataFrozen,
+        /// Returned if a burn would push the total supply below `min_supply`.
+        MinSupplyBreached,
+        /// Returned if the owner tries to lower `min_supply`.
+        MinSupplyLowered,
+        /// Returned if `mint` or `mint_batch` would exceed `mint_cap_per_block` in the current block.
+        MintRateExceeded,
+        /// Returned if a transfer only fits in the sender's balance when counting tokens still vesting or staked.
+        InsufficientUnlockedBalance,
+        /// Returned if the contract holds less than `emergency_withdraw` asks for.
+        InsufficientContractBalance,
+        /// Returned if anyone but the owner transfers before `launch_block`.
+        NotLaunchedYet,
+        /// Returned if `launch_block` is changed once it was reached or anyone
+        /// but the owner has transferred tokens.
+        AlreadyLaunched,
+        /// Returned if a transfer fee is enabled or dust is swept while no treasury is set.
+        NoTreasury,
+        /// Returned if `withdraw` asks for more native currency than the caller has deposited.
+        ExceedsDeposit,
+        /// Returned if `increase_allowance` or `decrease_allowance` is called for a spender with a periodic allowance.
+        PeriodicAllowance
     }
 
     pub type Result<T> = core::result::Result<T, Error>;
 
-    impl Erc20 {
-        /// Constructor that initializes the `bool` value to the given `init_value`.
-        #[ink(constructor)]
-        pub fn new(init_value: Balance) -> Self {
-            let caller = Self::env().caller();
-            let mut balances = HashMap::new();
-            balances.insert(caller, init_value);
+    /// Upper bound on the number of entries processed by a single batch message.
+    pub const MAX_BATCH_LEN: usize = 128;
 
-            Self::env().emit_event(
-                Transfer {
-                    from: None,
-                    to: Some(caller),
-                    value: init_value
-                }
-            );
+    /// Upper bound on the number of balances plus allowances `export_state` returns.
+    pub const MAX_EXPORT_ENTRIES: usize = 1024;
 
-            Self {
-                total_supply: Lazy::new(init_value),
-                balances,
-                allowances: HashMap::new()
-            }
-        }
+    /// Number of allowance changes `approval_history_of` keeps per owner.
+    pub const APPROVAL_HISTORY_LEN: usize = 8;
 
+    /// Number of digits in the fractional parts used by `to_base_units` and
+    /// `from_base_units`, independent of the token's decimals.
+    pub const FRACTION_DIGITS: u32 = 18;
 
-        #[ink(constructor)]
-        pub fn default() -> Self {
-            Self::new(Default::default())
+    /// Largest number of decimals a token can have: one whole token, 10^38
+    /// base units, is the largest power of ten a `Balance` holds.
+    pub const MAX_DECIMALS: u8 = 38;
+
+    /// Scale of `dividend_per_share`, so that small distributions over a
+    /// large supply do not round down to nothing.
+    pub const DIVIDEND_PRECISION: Balance = 1_000_000_000_000;
+
+    /// Scale of `reward_rate` and `reward_per_token`, so that rates below one
+    /// token per staked token and block are possible.
+    pub const REWARD_PRECISION: Balance = 1_000_000_000_000;
+
+    /// Number of blocks a governance proposal stays open for votes.
+    pub const VOTING_PERIOD: u32 = 100;
+
+    /// Share of the total supply, in basis points, that has to vote on a
+    /// proposal for it to pass.
+    pub const QUORUM_BPS: u16 = 1_000;
+
+    /// Four-byte selectors of the main messages, for tooling that builds raw
+    /// calls.
+    ///
+    /// The inherent messages carry the selectors ink! derives from their
+    /// names, pinned through explicit `selector` attributes like every other
+    /// message; the `PSP22_*` ones are those of the standard's trait messages.
+    pub mod selectors {
+        pub const TOTAL_SUPPLY: [u8; 4] = [0xdb, 0x63, 0x75, 0xa8];
+        pub const BALANCE_OF: [u8; 4] = [0x0f, 0x75, 0x5a, 0x56];
+        pub const ALLOWANCE: [u8; 4] = [0x6a, 0x00, 0x16, 0x5e];
+        pub const TRANSFER: [u8; 4] = [0x84, 0xa1, 0x5d, 0xa1];
+        pub const TRANSFER_FROM: [u8; 4] = [0x0b, 0x39, 0x6f, 0x18];
+        pub const APPROVE: [u8; 4] = [0x68, 0x12, 0x66, 0xa0];
+
+        pub const PSP22_TOTAL_SUPPLY: [u8; 4] = [0x16, 0x2d, 0xf8, 0xc2];
+        pub const PSP22_BALANCE_OF: [u8; 4] = [0x65, 0x68, 0x38, 0x2f];
+        pub const PSP22_ALLOWANCE: [u8; 4] = [0x4d, 0x47, 0xd9, 0x21];
+        pub const PSP22_TRANSFER: [u8; 4] = [0xdb, 0x20, 0xf9, 0xf5];
+        pub const PSP22_TRANSFER_FROM: [u8; 4] = [0x54, 0xb3, 0xc7, 0x6e];
+        pub const PSP22_APPROVE: [u8; 4] = [0xb2, 0x0f, 0x1b, 0xbd];
+        pub const PSP22_INCREASE_ALLOWANCE: [u8; 4] = [0x96, 0xd6, 0xb5, 0x7a];
+        pub const PSP22_DECREASE_ALLOWANCE: [u8; 4] = [0xfe, 0xcb, 0x57, 0xd5];
+    }
+
+    /// Identifies a role; by convention the first four bytes of the BLAKE2
+    /// hash of the role's name.
+    pub type RoleId = [u8; 4];
+
+    /// The total supply, every nonzero balance and every nonzero allowance, as
+    /// returned by `export_state`.
+    pub type ExportedState = (Balance, Vec<(AccountId, Balance)>, Vec<((AccountId, AccountId), Balance)>);
+
+    /// Administers every role, including itself.
+    pub const DEFAULT_ADMIN_ROLE: RoleId = [0x00; 4];
+
+    /// Allowed to `mint` new tokens.
+    pub const MINTER_ROLE: RoleId = [0xdd, 0x44, 0x72, 0x48];
+
+    /// Allowed to `unlock` tokens bridged in from another chain.
+    pub const RELAYER_ROLE: RoleId = [0xb2, 0x07, 0xeb, 0x99];
+
+    /// A privileged operation that needs `threshold` admin confirmations
+    /// once a multisig is set up.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout))]
+    pub enum AdminAction {
+        Mint { to: AccountId, value: Balance },
+        Pause,
+        Unpause,
+    }
+
+    /// How basis point shares of an amount are rounded to whole units.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout))]
+    pub enum RoundingMode {
+        Down,
+        Up,
+        /// Rounds halves up.
+        Nearest,
+    }
+
+    impl SpreadAllocate for RoundingMode {
+        fn allocate_spread(ptr: &mut ink_primitives::KeyPtr) -> Self {
+            ptr.advance_by(<Self as SpreadLayout>::FOOTPRINT);
+            RoundingMode::Down
         }
+    }
+
+    /// Snapshot of the limits and fees that currently apply, as returned by
+    /// `config`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct TokenConfig {
+        pub cap: Option<Balance>,
+        pub min_supply: Option<Balance>,
+        pub mint_cap_per_block: Option<Balance>,
+        pub max_tx_amount: Option<Balance>,
+        pub max_wallet_amount: Option<Balance>,
+        pub transfer_cooldown: u64,
+        pub large_transfer_threshold: Option<Balance>,
+        pub fee_bps: u16,
+        pub burn_bps: u16,
+        pub flash_fee_bps: u16,
+        pub rounding_mode: RoundingMode,
+        pub treasury: Option<AccountId>,
+        pub oracle: Option<AccountId>,
+        pub paused: bool,
+        pub trading_enabled: bool,
+        pub launch_block: Option<u32>,
+    }
+
+    /// A transfer waiting for its timelock to elapse.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout))]
+    pub struct QueuedTransfer {
+        pub from: AccountId,
+        pub to: AccountId,
+        pub value: Balance,
+        pub ready_at: u64,
+    }
+
+    /// Tokens that unlock linearly for a beneficiary between `start` and
+    /// `start + duration`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout))]
+    pub struct Vesting {
+        pub total: Balance,
+        pub released: Balance,
+        pub start: u64,
+        pub duration: u64,
+    }
+
+    /// A governance proposal; votes are weighed by the voting power at the
+    /// end of the block before `start_block`.
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout))]
+    pub struct Proposal {
+        pub proposer: AccountId,
+        pub description_hash: [u8; 32],
+        pub call_data: Vec<u8>,
+        pub start_block: u32,
+        pub end_block: u32,
+        pub quorum: Balance,
+        pub for_votes: Balance,
+        pub against_votes: Balance,
+        pub executed: bool,
+    }
+
+    /// The PSP22 fungible token standard.
+    ///
+    /// Selectors follow the PSP22 specification so that generic wallets and
+    /// routers can talk to the contract without knowing its inherent interface.
+    #[ink::trait_definition]
+    pub trait PSP22 {
+        #[ink(message, selector = 0x162df8c2)]
+        fn total_supply(&self) -> Balance;
+
+        #[ink(message, selector = 0x6568382f)]
+        fn balance_of(&self, owner: AccountId) -> Balance;
+
+        #[ink(message, selector = 0x4d47d921)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance;
 
+        #[ink(message, selector = 0xdb20f9f5)]
+        fn transfer(&mut self, to: AccountId, value: Balance, data: Vec<u8>) -> Result<()>;
 
+        #[ink(message, selector = 0x54b3c76e)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<()>;
+
+        #[ink(message, selector = 0xb20f1bbd)]
+        fn approve(&mut self, spender: AccountId, value: Balance) -> Result<()>;
+
+        #[ink(message, selector = 0x96d6b57a)]
+        fn increase_allowance(&mut self, spender: AccountId, delta: Balance) -> Result<()>;
+
+        #[ink(message, selector = 0xfecb57d5)]
+        fn decrease_allowance(&mut self, spender: AccountId, delta: Balance) -> Result<()>;
+    }
+
+    /// The optional PSP22 metadata extension.
+    #[ink::trait_definition]
+    pub trait PSP22Metadata {
+        #[ink(message, selector = 0x3d261bd4)]
+        fn token_name(&self) -> Option<String>;
+
+        #[ink(message, selector = 0x34205be5)]
+        fn token_symbol(&self) -> Option<String>;
+
+        #[ink(message, selector = 0x7271b782)]
+        fn token_decimals(&self) -> u8;
+    }
+
+    /// Value a `TokenReceiver` must return to accept incoming tokens.
+    pub const ON_TOKEN_RECEIVED_SELECTOR: [u8; 4] = [0xe1, 0x93, 0x61, 0x55];
+
+    /// Implemented by contracts that want to react to tokens sent through
+    /// `transfer_and_call`.
+    #[ink::trait_definition]
+    pub trait TokenReceiver {
+        /// Returns `ON_TOKEN_RECEIVED_SELECTOR` to accept the tokens.
+        #[ink(message, selector = 0xe1936155)]
+        fn on_token_received(
+            &mut self,
+            operator: AccountId,
+            from: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> [u8; 4];
+    }
+
+    /// Selector of `FeeOracle::get_fee_bps`.
+    pub const GET_FEE_BPS_SELECTOR: [u8; 4] = [0x32, 0x97, 0xba, 0x13];
+
+    /// Implemented by contracts that set the transfer fee of tokens using
+    /// them as `oracle`.
+    #[ink::trait_definition]
+    pub trait FeeOracle {
+        /// Returns the transfer fee in basis points.
+        #[ink(message, selector = 0x3297ba13)]
+        fn get_fee_bps(&self) -> u16;
+    }
+
+    /// Value a `FlashBorrower` must return to take a flash loan.
+    pub const ON_FLASH_LOAN_SELECTOR: [u8; 4] = [0x38, 0x09, 0x6f, 0xfe];
+
+    /// Implemented by contracts taking flash loans through `flash_mint`.
+    #[ink::trait_definition]
+    pub trait FlashBorrower {
+        /// Returns `ON_FLASH_LOAN_SELECTOR` once `amount + fee` is ready to be
+        /// taken back from the borrower.
+        #[ink(message, selector = 0x38096ffe)]
+        fn on_flash_loan(
+            &mut self,
+            initiator: AccountId,
+            amount: Balance,
+            fee: Balance,
+            data: Vec<u8>,
+        ) -> [u8; 4];
+    }
+
+    impl PSP22 for Erc20 {
         #[ink(message)]
-        pub fn total_supply(&self) -> Balance{
-            *self.total_supply
+        fn total_supply(&self) -> Balance {
+            self.total_supply
         }
 
         #[ink(message)]
-        pub fn balance_of(&self, who: AccountId) -> Balance {
-            self.balances.get(&who).copied().unwrap_or(0)
+        fn balance_of(&self, who: AccountId) -> Balance {
+            self.balances.get(who).unwrap_or(0)
         }
 
+        /// Returns zero once the allowance has passed its deadline.
         #[ink(message)]
-        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
-            self.allowances.get(&(owner, spender)).copied().unwrap_or(0)
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            if self.allowance_expired(owner, spender) {
+                return 0;
+            }
+            self.allowances.get((owner, spender)).unwrap_or(0)
         }
 
         #[ink(message)]
-        pub fn approve(&mut self, spender: AccountId, value: Balance) -> Result<()>{
+        fn approve(&mut self, spender: AccountId, value: Balance) -> Result<()> {
+            let owner = self.env().caller();
+            self.inter_approve(owner, spender, value)
+        }
+
+        /// Fails with `PeriodicAllowance` while `spender` has a periodic
+        /// allowance; `approve_periodic` or `approve` replaces it instead.
+        #[ink(message)]
+        fn increase_allowance(&mut self, spender: AccountId, delta: Balance) -> Result<()> {
+            self.ensure_not_paused()?;
+            self.ensure_not_zero_address(spender)?;
             let owner = self.env().caller();
-            self.allowances.insert((owner, spender), value);
+            self.ensure_not_frozen(owner)?;
+            self.ensure_not_periodic(owner, spender)?;
+            let allowance = self.allowance(owner, spender);
+            let new_allowance = allowance.checked_add(delta).ok_or(Error::Overflow)?;
+            // An expired allowance starts over from zero without a deadline,
+            // so that the new value is what `allowance` reports.
+            if self.allowance_expired(owner, spender) {
+                self.allowance_expiry.remove((owner, spender));
+            }
+            self.set_allowance(owner, spender, new_allowance);
 
             Self::env().emit_event(
                 Approval {
                     owner,
                     spender,
-                    value
+                    value: new_allowance
                 }
             );
 
             Ok(())
         }
 
+        /// Fails with `PeriodicAllowance` while `spender` has a periodic
+        /// allowance, like `increase_allowance`.
+        ///
+        /// A frozen owner cannot change allowances either way; it can still
+        /// withdraw one with `revoke_allowance`.
         #[ink(message)]
-        pub fn transfer(&mut self, to: AccountId, value: Balance) -> Result<()> {
-            let from = self.env().caller();
-            self.inter_transfer(from, to, value)
-        }
+        fn decrease_allowance(&mut self, spender: AccountId, delta: Balance) -> Result<()> {
+            self.ensure_not_paused()?;
+            let owner = self.env().caller();
+            self.ensure_not_frozen(owner)?;
+            self.ensure_not_periodic(owner, spender)?;
+            let allowance = self.allowance(owner, spender);
+            let new_allowance = allowance.checked_sub(delta).ok_or(Error::InsufficientApproval)?;
+            self.set_allowance(owner, spender, new_allowance);
+
+            Self::env().emit_event(
+                Approval {
+                    owner,
+                    spender,
+                    value: new_allowance
+                }
+            );
+
+            Ok(())
+        }
 
         #[ink(message)]
-        pub fn transfer_from(&mut self, from: AccountId, to: AccountId, value: Balance) -> Result<()> {
-            let caller = self.env().caller();
-            let allowance = self.allowance(from, caller);
+        fn transfer(&mut self, to: AccountId, value: Balance, _data: Vec<u8>) -> Result<()> {
+            let from = self.env().caller();
+            let fee_bps = self.current_fee_bps();
+            self.inter_transfer(from, to, value, fee_bps)
+        }
 
-            if value > allowance {
-                return Err(Error::InsufficientApproval);
+        /// Transfers `value` tokens on behalf of `from` to the account `to`.
+        ///
+        /// An allowance of `Balance::MAX` is treated as infinite: it is never
+        /// decreased and no follow-up `Approval` event is emitted for it.
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> Result<()> {
+            // Checked before the allowance so that a paused token always
+            // reports `Paused`, as `transfer` does.
+            self.ensure_not_paused()?;
+            let caller = self.env().caller();
+            let periodic = self.spend_periodic_allowance(from, caller, value)?;
+            if periodic.is_none() {
+                self.ensure_allowance(from, caller, value)?;
             }
-
-            self.inter_transfer(from, to, value)?;
+            let fee_bps = self.current_fee_bps();
+            self.inter_transfer(from, to, value, fee_bps)?;
 
             Self::env().emit_event(
                 TransferFrom {
@@ -140,630 +971,7838 @@ mod erc20 {
                 }
             );
 
-            let new_allowance = allowance - value;
+            match periodic {
+                Some(periodic) => {
+                    self.periodic_allowances.insert((from, caller), &periodic);
+                    Ok(())
+                }
+                None => self.spend_allowance(from, caller, value),
+            }
+        }
+    }
 
-            self.allowances.insert((from, caller), new_allowance);
+    impl PSP22Metadata for Erc20 {
+        #[ink(message)]
+        fn token_name(&self) -> Option<String> {
+            self.name.clone()
+        }
 
-            Self::env().emit_event(
-                Approval {
-                    owner: from,
-                    spender: caller,
-                    value
-                }
-            );
+        #[ink(message)]
+        fn token_symbol(&self) -> Option<String> {
+            self.symbol.clone()
+        }
 
-            Ok(())
+        #[ink(message)]
+        fn token_decimals(&self) -> u8 {
+            self.decimals
         }
+    }
 
-        fn inter_transfer(&mut self, from: AccountId, to: AccountId, value: Balance) -> Result<()> {
-            let from_balance: Balance = self.balances.get(&from).copied().unwrap_or(0);
+    /// Every constructor and message below pins its selector explicitly, so
+    /// renaming one never changes the ABI.
+    ///
+    /// The selectors are the ones ink! derived from the names they were added
+    /// under, the first four bytes of the BLAKE2-256 hash of the name; new
+    /// messages follow the same rule. The PSP22 trait messages keep the
+    /// standard's selectors, and `selectors` lists the most common ones.
+    impl Erc20 {
+        /// Constructor that mints `init_value` tokens to the caller.
+        ///
+        /// The token has no name or symbol and uses 18 decimals.
+        #[ink(constructor, selector = 0x9bae9d5e)]
+        pub fn new(init_value: Balance) -> Self {
+            Self::new_with_metadata(init_value, None, None, 18)
+        }
 
-            if value > from_balance {
-                return Err(Error::InsufficientBalance);
-            }
+        /// Constructor that mints `init_value` tokens to the caller and
+        /// stores the given token metadata.
+        ///
+        /// # Panics
+        ///
+        /// If `decimals` is larger than `MAX_DECIMALS`.
+        #[ink(constructor, selector = 0xc65bc726)]
+        pub fn new_with_metadata(
+            init_value: Balance,
+            name: Option<String>,
+            symbol: Option<String>,
+            decimals: u8,
+        ) -> Self {
+            let caller = Self::env().caller();
+            Self::allocate(ink_prelude::vec![(caller, init_value)], name, symbol, decimals)
+        }
 
-            let to_balance = self.balances.get(&to).copied().unwrap_or(0);
+        /// Like `new_with_metadata`, but the metadata can never be changed.
+        #[ink(constructor, selector = 0x47ee9dd6)]
+        pub fn new_immutable(
+            init_value: Balance,
+            name: Option<String>,
+            symbol: Option<String>,
+            decimals: u8,
+        ) -> Self {
+            let mut contract = Self::new_with_metadata(init_value, name, symbol, decimals);
+            contract.metadata_frozen = true;
+            contract
+        }
 
-            self.balances.insert(from, from_balance - value);
-            self.balances.insert(to, to_balance + value);
+        /// Like `new_with_metadata`, but spelled out for tokens whose owner
+        /// renames them through `set_name` and `set_symbol` until calling
+        /// `freeze_metadata`.
+        #[ink(constructor, selector = 0x940c6b97)]
+        pub fn new_mutable(
+            init_value: Balance,
+            name: Option<String>,
+            symbol: Option<String>,
+            decimals: u8,
+        ) -> Self {
+            Self::new_with_metadata(init_value, name, symbol, decimals)
+        }
 
-            Self::env().emit_event(
-                Transfer {
-                    from: Some(from),
-                    to: Some(to),
-                    value
+        /// Constructor that credits every `(account, value)` pair of
+        /// `allocations` instead of minting to the caller, who still becomes
+        /// the owner.
+        ///
+        /// # Panics
+        ///
+        /// If `allocations` is empty, names an account twice or adds up to
+        /// more than `Balance::MAX`.
+        #[ink(constructor, selector = 0x493852af)]
+        pub fn new_distributed(allocations: Vec<(AccountId, Balance)>) -> Self {
+            assert!(!allocations.is_empty(), "no allocations given");
+            for (index, (account, _)) in allocations.iter().enumerate() {
+                assert!(
+                    !allocations[..index].iter().any(|(other, _)| other == account),
+                    "duplicate allocation"
+                );
+            }
+            Self::allocate(allocations, None, None, 18)
+        }
+
+        /// Shared body of the constructors; expects distinct accounts.
+        fn allocate(
+            allocations: Vec<(AccountId, Balance)>,
+            name: Option<String>,
+            symbol: Option<String>,
+            decimals: u8,
+        ) -> Self {
+            assert!(decimals <= MAX_DECIMALS, "too many decimals");
+            // Initializes the `Mapping`s before any of them is written to.
+            ink_lang::utils::initialize_contract(|contract: &mut Self| {
+                let caller = Self::env().caller();
+                let mut total_supply: Balance = 0;
+                for (account, value) in allocations {
+                    total_supply = total_supply.checked_add(value).expect("initial supply overflows");
+                    contract.set_balance(account, value);
+
+                    Self::env().emit_event(
+                        Transfer {
+                            from: None,
+                            to: Some(account),
+                            value
+                        }
+                    );
                 }
-            );
+                contract.total_supply = total_supply;
+                contract.total_minted = total_supply;
+                contract.name = name;
+                contract.symbol = symbol;
+                contract.decimals = decimals;
+                contract.owner = caller;
+                contract.rounding_mode = RoundingMode::Down;
+                contract.roles.insert((DEFAULT_ADMIN_ROLE, caller), &true);
+                contract.roles.insert((MINTER_ROLE, caller), &true);
+                contract.roles.insert((RELAYER_ROLE, caller), &true);
+                contract.check_zero_address = true;
+                contract.trading_enabled = true;
+            })
+        }
 
-            Ok(())
+
+        /// Constructor that mints `init_value` tokens to the caller and never
+        /// lets the total supply grow beyond `cap`.
+        ///
+        /// # Panics
+        ///
+        /// If `init_value` is larger than `cap`.
+        #[ink(constructor, selector = 0x986a7fc7)]
+        pub fn new_capped(init_value: Balance, cap: Balance) -> Self {
+            assert!(init_value <= cap, "initial supply exceeds the cap");
+            let mut contract = Self::new(init_value);
+            contract.cap = Some(cap);
+            contract
         }
 
-    }
+        /// Constructor that mints `init_value` tokens to the caller and lets
+        /// the caller opt out of rejecting the zero account as recipient or spender.
+        #[ink(constructor, selector = 0x4cc1c2d6)]
+        pub fn new_with_zero_address_check(init_value: Balance, check_zero_address: bool) -> Self {
+            let mut contract = Self::new(init_value);
+            contract.check_zero_address = check_zero_address;
+            contract
+        }
 
-    /// Unit tests.
-    #[cfg(not(feature = "ink-experimental-engine"))]
-    #[cfg(test)]
-    mod tests {
-        /// Imports all the definitions from the outer scope so we can use them here.
-        use super::*;
+        /// Constructor that mints `init_value` tokens to the caller and charges
+        /// `flash_fee_bps` basis points on every flash loan.
+        ///
+        /// # Panics
+        ///
+        /// If `flash_fee_bps` is larger than 10 000.
+        #[ink(constructor, selector = 0xc8ab539b)]
+        pub fn new_with_flash_fee(init_value: Balance, flash_fee_bps: u16) -> Self {
+            assert!(flash_fee_bps <= 10_000, "flash fee exceeds 100%");
+            let mut contract = Self::new(init_value);
+            contract.flash_fee_bps = flash_fee_bps;
+            contract
+        }
 
-        type Event = <Erc20 as ::ink_lang::BaseEvent>::Type;
+        /// Constructor that mints `init_value` tokens to the caller and routes
+        /// `fee_bps` basis points of every transfer to `treasury`.
+        ///
+        /// # Panics
+        ///
+        /// If `fee_bps` is larger than 10 000.
+        #[ink(constructor, selector = 0xc120c88f)]
+        pub fn new_with_fee(init_value: Balance, fee_bps: u16, treasury: AccountId) -> Self {
+            assert!(fee_bps <= 10_000, "transfer fee exceeds 100%");
+            let mut contract = Self::new(init_value);
+            contract.fee_bps = fee_bps;
+            contract.treasury = Some(treasury);
+            contract
+        }
 
-        use ink_lang as ink;
+        /// Constructor that mints `init_value` tokens to the caller and reports
+        /// later mints and burns with `Mint` and `Burn` events.
+        ///
+        /// If `replace_transfers` is set, those mints and burns no longer emit
+        /// `Transfer` events from or to nobody. The initial supply is always
+        /// reported as a `Transfer`.
+        #[ink(constructor, selector = 0x23bba999)]
+        pub fn new_with_mint_burn_events(init_value: Balance, replace_transfers: bool) -> Self {
+            let mut contract = Self::new(init_value);
+            contract.mint_burn_events = true;
+            contract.skip_zero_address_transfers = replace_transfers;
+            contract
+        }
 
-        fn assert_transfer_event(
-            event: &ink_env::test::EmittedEvent,
-            expected_from: Option<AccountId>,
-            expected_to: Option<AccountId>,
-            expected_value: Balance,
-        ) {
-            let decoded_event = <Event as scale::Decode>::decode(&mut &event.data[..])
-                .expect("encountered invalid contract event data buffer");
-            if let Event::Transfer(Transfer { from, to, value }) = decoded_event {
-                assert_eq!(from, expected_from, "encountered invalid Transfer.from");
-                assert_eq!(to, expected_to, "encountered invalid Transfer.to");
-                assert_eq!(value, expected_value, "encountered invalid Trasfer.value");
+        /// Constructor that mints `init_value` tokens to the caller and never
+        /// lets burns take the total supply below `min_supply`.
+        #[ink(constructor, selector = 0x97c0ba42)]
+        pub fn new_with_min_supply(init_value: Balance, min_supply: Balance) -> Self {
+            let mut contract = Self::new(init_value);
+            contract.min_supply = Some(min_supply);
+            contract
+        }
+
+        /// Constructor that mints `init_value` tokens to the caller and only
+        /// allows transfers from or to whitelisted accounts until the owner
+        /// calls `enable_trading`.
+        ///
+        /// The caller starts out whitelisted so it can distribute the supply.
+        #[ink(constructor, selector = 0x0a9e3cad)]
+        pub fn new_prelaunch(init_value: Balance) -> Self {
+            let mut contract = Self::new(init_value);
+            let caller = Self::env().caller();
+            contract.trading_enabled = false;
+            contract.transfer_whitelist.insert(caller, &true);
+            contract
+        }
+
+        /// Constructor that mints `init_value` tokens to the caller and
+        /// delegates every account to itself when it first receives tokens.
+        ///
+        /// Accounts that delegated before their first receipt keep their
+        /// choice, and `delegate` can move the votes elsewhere at any time.
+        #[ink(constructor, selector = 0x5d0924c2)]
+        pub fn new_with_auto_delegation(init_value: Balance) -> Self {
+            let mut contract = Self::new(init_value);
+            let caller = Self::env().caller();
+            contract.auto_self_delegate = true;
+            contract.inter_delegate(caller, caller);
+            contract
+        }
+
+        /// Constructor that mints `init_value` tokens to the caller and emits
+        /// `TransferFailed` whenever a transfer fails.
+        ///
+        /// The event's `reason` is the index of the `Error` variant. Since a
+        /// failed message is reverted on chain, the event is only seen by dry
+        /// runs and off-chain tooling.
+        #[ink(constructor, selector = 0xe8d91acd)]
+        pub fn new_with_failure_events(init_value: Balance) -> Self {
+            let mut contract = Self::new(init_value);
+            contract.emit_failures = true;
+            contract
+        }
+
+        /// Constructor that mints `init_value` tokens to the caller and emits
+        /// `TransferIndexed` along with every `Transfer`.
+        #[ink(constructor, selector = 0x42f69bf8)]
+        pub fn new_with_indexed_values(init_value: Balance) -> Self {
+            let mut contract = Self::new(init_value);
+            contract.index_values = true;
+            contract.emit_indexed_transfer(None, Some(Self::env().caller()), init_value);
+            contract
+        }
+
+        /// Constructor that mints `init_value` tokens to the caller and lets
+        /// `spender`, such as a DEX router, spend `allowance` of them.
+        ///
+        /// # Panics
+        ///
+        /// If `spender` is the zero account.
+        #[ink(constructor, selector = 0xbc7da8af)]
+        pub fn new_with_preapproval(init_value: Balance, spender: AccountId, allowance: Balance) -> Self {
+            let mut contract = Self::new(init_value);
+            let caller = Self::env().caller();
+            contract
+                .inter_approve(caller, spender, allowance)
+                .expect("spender is the zero account");
+            contract
+        }
+
+        #[ink(constructor, selector = 0xed4b9d1b)]
+        #[allow(clippy::should_implement_trait)]
+        pub fn default() -> Self {
+            Self::new(Default::default())
+        }
+
+
+        #[ink(message, selector = 0xdb6375a8)]
+        pub fn total_supply(&self) -> Balance{
+            PSP22::total_supply(self)
+        }
+
+        /// Returns the total supply minus the balances of all excluded accounts.
+        #[ink(message, selector = 0x65e7d3cf)]
+        pub fn circulating_supply(&self) -> Balance {
+            let excluded = self.excluded
+                .iter()
+                .fold(0 as Balance, |sum, account| sum.saturating_add(self.balance_of(*account)));
+            // The excluded balances are part of the supply, so this never
+            // falls back in practice.
+            self.total_supply().saturating_sub(excluded)
+        }
+
+        #[ink(message, selector = 0xca43012c)]
+        pub fn excluded_accounts(&self) -> Vec<AccountId> {
+            self.excluded.clone()
+        }
+
+        /// Returns all tokens ever minted, including the initial supply.
+        #[ink(message, selector = 0x16af933a)]
+        pub fn total_minted(&self) -> Balance {
+            self.total_minted
+        }
+
+        /// Returns all tokens ever burned.
+        #[ink(message, selector = 0xed9f90c1)]
+        pub fn total_burned(&self) -> Balance {
+            self.total_burned
+        }
+
+        #[ink(message, selector = 0xb00b03c6)]
+        pub fn cap(&self) -> Option<Balance> {
+            self.cap
+        }
+
+        #[ink(message, selector = 0x53db29ae)]
+        pub fn token_name(&self) -> Option<String> {
+            PSP22Metadata::token_name(self)
+        }
+
+        #[ink(message, selector = 0xd733ea17)]
+        pub fn token_symbol(&self) -> Option<String> {
+            PSP22Metadata::token_symbol(self)
+        }
+
+        #[ink(message, selector = 0x9715e3ca)]
+        pub fn token_decimals(&self) -> u8 {
+            PSP22Metadata::token_decimals(self)
+        }
+
+        /// Converts `whole` tokens plus a fraction of `frac` / 10^`FRACTION_DIGITS`
+        /// tokens into base units, dropping precision beyond the token's decimals.
+        ///
+        /// Saturates at `Balance::MAX`.
+        #[ink(message, selector = 0xd285c2eb)]
+        pub fn to_base_units(&self, whole: u128, frac: u128) -> Balance {
+            let decimals = self.decimals as u32;
+            let frac_units = if decimals <= FRACTION_DIGITS {
+                frac / Self::pow10(FRACTION_DIGITS - decimals).unwrap_or(Balance::MAX)
             } else {
-                panic!("encountered unexpected event kind: expected a Transfer event")
+                frac.saturating_mul(Self::pow10(decimals - FRACTION_DIGITS).unwrap_or(Balance::MAX))
+            };
+            whole
+                .saturating_mul(Self::pow10(decimals).unwrap_or(Balance::MAX))
+                .saturating_add(frac_units)
+        }
+
+        /// Splits `amount` base units into whole tokens and a fraction in
+        /// units of 10^-`FRACTION_DIGITS` tokens, as taken by `to_base_units`.
+        #[ink(message, selector = 0x41716f26)]
+        #[allow(clippy::wrong_self_convention)]
+        pub fn from_base_units(&self, amount: Balance) -> (u128, u128) {
+            let decimals = self.decimals as u32;
+            let unit = Self::pow10(decimals).expect("decimals are at most MAX_DECIMALS");
+            let (whole, rest) = (amount / unit, amount % unit);
+            let frac = if decimals <= FRACTION_DIGITS {
+                rest * Self::pow10(FRACTION_DIGITS - decimals).unwrap_or(0)
+            } else {
+                rest / Self::pow10(decimals - FRACTION_DIGITS).unwrap_or(Balance::MAX)
+            };
+            (whole, frac)
+        }
+
+        #[ink(message, selector = 0x0f755a56)]
+        pub fn balance_of(&self, who: AccountId) -> Balance {
+            PSP22::balance_of(self, who)
+        }
+
+        #[ink(message, selector = 0x6a00165e)]
+        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            PSP22::allowance(self, owner, spender)
+        }
+
+        /// Returns every limit and fee in one read.
+        #[ink(message, selector = 0x70714744)]
+        pub fn config(&self) -> TokenConfig {
+            TokenConfig {
+                cap: self.cap,
+                min_supply: self.min_supply,
+                mint_cap_per_block: self.mint_cap_per_block,
+                max_tx_amount: self.max_tx_amount,
+                max_wallet_amount: self.max_wallet_amount,
+                transfer_cooldown: self.transfer_cooldown,
+                large_transfer_threshold: self.large_transfer_threshold,
+                fee_bps: self.fee_bps,
+                burn_bps: self.burn_bps,
+                flash_fee_bps: self.flash_fee_bps,
+                rounding_mode: self.rounding_mode,
+                treasury: self.treasury,
+                oracle: self.oracle,
+                paused: self.paused,
+                trading_enabled: self.trading_enabled,
+                launch_block: self.launch_block,
             }
-            let expected_topics = vec![
-                encoded_into_hash(&PrefixedValue {
-                    value: b"Erc20::Transfer",
-                    prefix: b"",
-                }),
-                encoded_into_hash(&PrefixedValue {
-                    prefix: b"Erc20::Transfer::from",
-                    value: &expected_from,
-                }),
-                encoded_into_hash(&PrefixedValue {
-                    prefix: b"Erc20::Transfer::to",
-                    value: &expected_to,
-                }),
-                encoded_into_hash(&PrefixedValue {
-                    prefix: b"Erc20::Transfer::value",
-                    value: &expected_value,
-                }),
-            ];
-            for (n, (actual_topic, expected_topic)) in
-            event.topics.iter().zip(expected_topics).enumerate()
-            {
-                let topic = actual_topic
-                    .decode::<Hash>()
-                    .expect("encountered invalid topic encoding");
-                assert_eq!(topic, expected_topic, "encountered invalid topic at {}", n);
+        }
+
+        /// Returns the balance of `who` together with the allowance of `spender`
+        /// over it.
+        #[ink(message, selector = 0x74d447f5)]
+        pub fn account_state(&self, who: AccountId, spender: AccountId) -> (Balance, Balance) {
+            (self.balance_of(who), self.allowance(who, spender))
+        }
+
+        /// Returns the total supply, every nonzero balance and every nonzero
+        /// allowance.
+        ///
+        /// Reads every holder and every approved pair, so its cost grows with
+        /// both and it fails with `TooManyEntries` past `MAX_EXPORT_ENTRIES`.
+        #[ink(message, selector = 0xc6e9a2b9)]
+        pub fn export_state(&self) -> Result<ExportedState> {
+            if self.holder_count as usize + self.allowance_pair_count as usize > MAX_EXPORT_ENTRIES {
+                return Err(Error::TooManyEntries);
             }
+            let balances = self.all_holders()
+                .iter()
+                .map(|holder| (*holder, self.balance_of(*holder)))
+                .collect();
+            let allowances = (0..self.allowance_pair_count)
+                .filter_map(|index| self.allowance_pairs.get(index))
+                .map(|(owner, spender)| ((owner, spender), self.allowance(owner, spender)))
+                .filter(|(_, value)| *value > 0)
+                .collect();
+            Ok((self.total_supply(), balances, allowances))
+        }
+
+        /// Returns the sum of the allowances `owner` has granted.
+        ///
+        /// Saturates at `Balance::MAX`, so an infinite allowance makes the
+        /// total infinite as well.
+        #[ink(message, selector = 0x984e5c34)]
+        pub fn total_allowance_granted(&self, owner: AccountId) -> Balance {
+            (0..self.spender_count.get(owner).unwrap_or(0))
+                .filter_map(|index| self.spenders.get((owner, index)))
+                .fold(0, |total: Balance, spender| total.saturating_add(self.allowance(owner, spender)))
+        }
+
+        /// Returns the balance of every account in `accounts`, in order.
+        ///
+        /// Fails with `BatchTooLarge` past `MAX_BATCH_LEN` accounts.
+        #[ink(message, selector = 0x97d5f295)]
+        pub fn balances_of(&self, accounts: Vec<AccountId>) -> Result<Vec<Balance>> {
+            if accounts.len() > MAX_BATCH_LEN {
+                return Err(Error::BatchTooLarge);
+            }
+            Ok(accounts.iter().map(|account| self.balance_of(*account)).collect())
+        }
+
+        /// Returns the allowance of every spender in `spenders` over the
+        /// tokens of `owner`, in order.
+        ///
+        /// Fails with `BatchTooLarge` past `MAX_BATCH_LEN` spenders.
+        #[ink(message, selector = 0x3ed08818)]
+        pub fn allowances_of(&self, owner: AccountId, spenders: Vec<AccountId>) -> Result<Vec<Balance>> {
+            if spenders.len() > MAX_BATCH_LEN {
+                return Err(Error::BatchTooLarge);
+            }
+            Ok(spenders.iter().map(|spender| self.allowance(owner, *spender)).collect())
+        }
+
+        /// Returns whether the balances of all holders add up to the total
+        /// supply.
+        ///
+        /// A debugging aid for tests and audits rather than a message: ink! 3
+        /// cannot compile messages conditionally.
+        #[cfg(feature = "std")]
+        pub fn verify_supply_invariant(&self) -> bool {
+            let sum = self.all_holders()
+                .iter()
+                .try_fold(0 as Balance, |sum, holder| sum.checked_add(self.balance_of(*holder)));
+            sum == Some(self.total_supply())
+        }
+
+        /// Returns up to `n` holders with their balances, largest first.
+        ///
+        /// Sorting costs O(h log h) in the number of holders, so this fails
+        /// with `TooManyEntries` if `n` or the holder count exceed
+        /// `MAX_EXPORT_ENTRIES`.
+        #[ink(message, selector = 0x2325bcf8)]
+        pub fn top_holders(&self, n: u32) -> Result<Vec<(AccountId, Balance)>> {
+            if n as usize > MAX_EXPORT_ENTRIES || self.holder_count as usize > MAX_EXPORT_ENTRIES {
+                return Err(Error::TooManyEntries);
+            }
+            let mut holders: Vec<(AccountId, Balance)> = self.all_holders()
+                .iter()
+                .map(|holder| (*holder, self.balance_of(*holder)))
+                .filter(|(_, balance)| *balance > 0)
+                .collect();
+            holders.sort_by(|(_, a), (_, b)| b.cmp(a));
+            holders.truncate(n as usize);
+            Ok(holders)
+        }
+
+        /// Returns the last `APPROVAL_HISTORY_LEN` allowance changes of
+        /// `owner` as `(spender, value, timestamp)`, oldest first.
+        ///
+        /// Allowances spent by `transfer_from` or `burn_from` are recorded as
+        /// well.
+        #[ink(message, selector = 0x5262b477)]
+        pub fn approval_history_of(&self, owner: AccountId) -> Vec<(AccountId, Balance, u64)> {
+            self.approval_history.get(owner).unwrap_or_default()
+        }
+
+        /// Returns the timestamp after which the allowance of `spender` over
+        /// the tokens of `owner` stops working, if any.
+        ///
+        /// Increasing or decreasing an allowance keeps its deadline, unless it
+        /// has passed: an expired allowance that is increased has none.
+        #[ink(message, selector = 0x7a6a5222)]
+        pub fn allowance_deadline(&self, owner: AccountId, spender: AccountId) -> Option<u64> {
+            self.allowance_expiry.get((owner, spender))
+        }
+
+        /// Returns the `(limit, period, used, window_start)` of a periodic
+        /// allowance; `period` is in seconds, `window_start` a timestamp.
+        #[ink(message, selector = 0x38dcfd3a)]
+        pub fn periodic_allowance(
+            &self,
+            owner: AccountId,
+            spender: AccountId,
+        ) -> Option<(Balance, u64, Balance, u64)> {
+            self.periodic_allowances.get((owner, spender))
+        }
+
+        /// Returns the number of accounts holding a nonzero balance.
+        #[ink(message, selector = 0xce83a421)]
+        pub fn holder_count(&self) -> u32 {
+            self.holder_count
+        }
+
+        /// Returns up to `len` holders starting at position `start`, capped at
+        /// `MAX_BATCH_LEN` entries.
+        ///
+        /// The order is unspecified and changes whenever an account stops
+        /// holding tokens.
+        #[ink(message, selector = 0x5bf8347a)]
+        pub fn holders_page(&self, start: u32, len: u32) -> Vec<AccountId> {
+            let end = start
+                .saturating_add(core::cmp::min(len as usize, MAX_BATCH_LEN) as u32)
+                .min(self.holder_count);
+            (start..end).filter_map(|index| self.holders.get(index)).collect()
+        }
+
+        #[ink(message, selector = 0x37704822)]
+        pub fn transfer_cooldown(&self) -> u64 {
+            self.transfer_cooldown
+        }
+
+        #[ink(message, selector = 0xed2ef3f5)]
+        pub fn max_tx_amount(&self) -> Option<Balance> {
+            self.max_tx_amount
+        }
+
+        /// Returns the dividends `account` can claim right now.
+        #[ink(message, selector = 0x96145fcd)]
+        pub fn dividends_of(&self, account: AccountId) -> Balance {
+            self.unclaimed_dividends.get(account).unwrap_or(0)
+                .saturating_add(self.new_dividends(account, self.balance_of(account)))
+        }
+
+        /// Returns the dividends `account` has claimed so far.
+        #[ink(message, selector = 0xad4d2314)]
+        pub fn withdrawn_dividends(&self, account: AccountId) -> Balance {
+            self.withdrawn_dividends.get(account).unwrap_or(0)
+        }
+
+        /// Returns the native currency `buy` charges per base unit.
+        #[ink(message, selector = 0x9fd7be02)]
+        pub fn price_per_token(&self) -> Balance {
+            self.price_per_token
+        }
+
+        /// Returns the native currency waiting for `account` in `claim_native`.
+        #[ink(message, selector = 0x6fba8277)]
+        pub fn pending_withdrawal(&self, account: AccountId) -> Balance {
+            self.pending_withdrawals.get(account).unwrap_or(0)
+        }
+
+        /// Returns the native currency held for tokens minted by `deposit`.
+        #[ink(message, selector = 0xb5405940)]
+        pub fn wrapped_native(&self) -> Balance {
+            self.wrapped_native
+        }
+
+        /// Returns the native currency `account` has deposited and not yet
+        /// withdrawn.
+        #[ink(message, selector = 0x04342b85)]
+        pub fn native_deposit(&self, account: AccountId) -> Balance {
+            self.native_deposits.get(account).unwrap_or(0)
+        }
+
+        #[ink(message, selector = 0xf5739f76)]
+        pub fn mint_cap_per_block(&self) -> Option<Balance> {
+            self.mint_cap_per_block
+        }
+
+        #[ink(message, selector = 0x0bcc17e6)]
+        pub fn oracle(&self) -> Option<AccountId> {
+            self.oracle
+        }
+
+        #[ink(message, selector = 0x57f22676)]
+        pub fn min_supply(&self) -> Option<Balance> {
+            self.min_supply
+        }
+
+        #[ink(message, selector = 0x2020513c)]
+        pub fn metadata_frozen(&self) -> bool {
+            self.metadata_frozen
+        }
+
+        #[ink(message, selector = 0x87b6473b)]
+        pub fn trading_enabled(&self) -> bool {
+            self.trading_enabled
+        }
+
+        #[ink(message, selector = 0x7f87bb88)]
+        pub fn launch_block(&self) -> Option<u32> {
+            self.launch_block
+        }
+
+        #[ink(message, selector = 0xe303952a)]
+        pub fn is_whitelisted(&self, account: AccountId) -> bool {
+            self.transfer_whitelist.get(account).unwrap_or(false)
+        }
+
+        #[ink(message, selector = 0x8bac7f26)]
+        pub fn migration_complete(&self) -> bool {
+            self.migration_complete
+        }
+
+        #[ink(message, selector = 0xbdd42373)]
+        pub fn sale_active(&self) -> bool {
+            self.sale_active
+        }
+
+        #[ink(message, selector = 0xc340ddd6)]
+        pub fn max_wallet_amount(&self) -> Option<Balance> {
+            self.max_wallet_amount
+        }
+
+        #[ink(message, selector = 0xfa11231e)]
+        pub fn large_transfer_threshold(&self) -> Option<Balance> {
+            self.large_transfer_threshold
+        }
+
+        #[ink(message, selector = 0x7b815f1f)]
+        pub fn queued_transfer(&self, id: u64) -> Option<QueuedTransfer> {
+            self.queued_transfers.get(id)
+        }
+
+        #[ink(message, selector = 0xe3ff18a2)]
+        pub fn vesting_of(&self, beneficiary: AccountId) -> Option<Vesting> {
+            self.vestings.get(beneficiary)
+        }
+
+        /// Returns the amount `beneficiary` has vested but not yet released.
+        #[ink(message, selector = 0xe0875381)]
+        pub fn releasable(&self, beneficiary: AccountId) -> Balance {
+            let vesting = match self.vestings.get(beneficiary) {
+                Some(vesting) => vesting,
+                None => return 0,
+            };
+            let now = self.block_timestamp();
+            let vested = if now < vesting.start {
+                0
+            } else if now - vesting.start >= vesting.duration {
+                vesting.total
+            } else {
+                // Split up so that `total * elapsed` cannot overflow.
+                let elapsed = (now - vesting.start) as Balance;
+                let duration = vesting.duration as Balance;
+                vesting.total / duration * elapsed + vesting.total % duration * elapsed / duration
+            };
+            vested - vesting.released
+        }
+
+        /// Returns the native currency held by the contract.
+        #[ink(message, selector = 0xcb0e708d)]
+        pub fn native_balance(&self) -> Balance {
+            self.env().balance()
+        }
+
+        #[ink(message, selector = 0x3072ed27)]
+        pub fn emission_per_block(&self) -> Balance {
+            self.emission_per_block
+        }
+
+        #[ink(message, selector = 0xd4aa4ff4)]
+        pub fn emission_recipient(&self) -> AccountId {
+            self.emission_recipient
+        }
+
+        #[ink(message, selector = 0x6c0909ba)]
+        pub fn reward_rate(&self) -> Balance {
+            self.reward_rate
+        }
+
+        #[ink(message, selector = 0xb7d69a40)]
+        pub fn staked_of(&self, account: AccountId) -> Balance {
+            self.staked.get(account).map(|(amount, _)| amount).unwrap_or(0)
+        }
+
+        /// Returns the rewards `account` would receive from `claim_rewards` now.
+        #[ink(message, selector = 0x46daed48)]
+        pub fn pending_rewards(&self, account: AccountId) -> Balance {
+            self.accrued_rewards
+                .get(account)
+                .unwrap_or(0)
+                .saturating_add(self.new_rewards(account))
+        }
+
+        #[ink(message, selector = 0xfeaea4fa)]
+        pub fn owner(&self) -> AccountId {
+            self.owner
+        }
+
+        /// Returns the hash every signed message of this contract starts with,
+        /// so that signatures cannot be replayed against another deployment.
+        #[ink(message, selector = 0xb7f73b4a)]
+        pub fn domain_separator(&self) -> [u8; 32] {
+            self.build_domain_separator()
+        }
+
+        #[ink(message, selector = 0x32544995)]
+        pub fn nonce_of(&self, owner: AccountId) -> u64 {
+            self.nonces.get(owner).unwrap_or(0)
+        }
+
+        /// Returns the balance of `who` at the time snapshot `snapshot_id` was taken.
+        ///
+        /// Ids that were never handed out by `snapshot` read as the current balance.
+        #[ink(message, selector = 0x5a2f8344)]
+        pub fn balance_of_at(&self, who: AccountId, snapshot_id: u64) -> Balance {
+            Self::value_at(
+                self.account_snapshot_count.get(who).unwrap_or(0),
+                |index| self.account_snapshots.get((who, index)),
+                snapshot_id,
+                self.current_snapshot_id,
+            )
+                .unwrap_or_else(|| self.balance_of(who))
+        }
+
+        /// Returns the total supply at the time snapshot `id` was taken.
+        #[ink(message, selector = 0x3727369d)]
+        pub fn total_supply_at(&self, id: u64) -> Balance {
+            Self::value_at(
+                self.total_supply_snapshot_count,
+                |index| self.total_supply_snapshots.get(index),
+                id,
+                self.current_snapshot_id,
+            )
+                .unwrap_or_else(|| self.total_supply())
+        }
+
+        /// Returns the account `account` delegates its votes to, or the zero
+        /// account if it never delegated.
+        #[ink(message, selector = 0x804c53d9)]
+        pub fn delegates(&self, account: AccountId) -> AccountId {
+            self.delegate_of(account).unwrap_or_else(|| AccountId::from([0u8; 32]))
+        }
+
+        #[ink(message, selector = 0x5f9d374c)]
+        pub fn get_votes(&self, account: AccountId) -> Balance {
+            self.vote_checkpoint_count
+                .get(account)
+                .and_then(|len| len.checked_sub(1))
+                .and_then(|index| self.vote_checkpoints.get((account, index)))
+                .map(|(_, votes)| votes)
+                .unwrap_or(0)
+        }
+
+        /// Returns the voting power of `account` at the end of block `block`.
+        #[ink(message, selector = 0x7995d5fc)]
+        pub fn get_past_votes(&self, account: AccountId, block: u32) -> Balance {
+            let len = self.vote_checkpoint_count.get(account).unwrap_or(0);
+            let index = Self::partition_point(len, |index| {
+                self.vote_checkpoints
+                    .get((account, index))
+                    .is_some_and(|(checkpoint_block, _)| checkpoint_block <= block)
+            });
+            index
+                .checked_sub(1)
+                .and_then(|index| self.vote_checkpoints.get((account, index)))
+                .map(|(_, votes)| votes)
+                .unwrap_or(0)
+        }
+
+        /// Returns the sum of balance × milliseconds that `who` held from
+        /// timestamp `since` until now.
+        ///
+        /// Dividing by the elapsed time gives the average balance over that
+        /// period; time before the first balance change counts as zero.
+        #[ink(message, selector = 0x64c35619)]
+        pub fn time_weighted_balance(&self, who: AccountId, since: u64) -> u128 {
+            let now = self.block_timestamp();
+            if since >= now {
+                return 0;
+            }
+            self.weight_at(who, now).saturating_sub(self.weight_at(who, since))
+        }
+
+        #[ink(message, selector = 0x025aac7e)]
+        pub fn proposal(&self, proposal_id: u64) -> Option<Proposal> {
+            self.proposals.get(proposal_id)
+        }
+
+        #[ink(message, selector = 0xb03e31b0)]
+        pub fn has_voted(&self, proposal_id: u64, account: AccountId) -> bool {
+            self.proposal_votes.get((proposal_id, account)).unwrap_or(false)
+        }
+
+        /// Returns the largest amount `flash_mint` can currently lend.
+        #[ink(message, selector = 0xc6b8bd9f)]
+        pub fn max_flash_loan(&self) -> Balance {
+            self.cap.unwrap_or(Balance::MAX).saturating_sub(self.total_supply())
+        }
+
+        #[ink(message, selector = 0x735693b6)]
+        pub fn flash_fee(&self, amount: Balance) -> Balance {
+            self.apply_bps(amount, self.flash_fee_bps)
+        }
+
+        #[ink(message, selector = 0xfd56f1d0)]
+        pub fn rounding_mode(&self) -> RoundingMode {
+            self.rounding_mode
+        }
+
+        #[ink(message, selector = 0xf18316c5)]
+        pub fn fee_bps(&self) -> u16 {
+            self.fee_bps
+        }
+
+        #[ink(message, selector = 0x1fb21571)]
+        pub fn burn_bps(&self) -> u16 {
+            self.burn_bps
+        }
+
+        #[ink(message, selector = 0xa9b78461)]
+        pub fn treasury(&self) -> Option<AccountId> {
+            self.treasury
+        }
+
+        #[ink(message, selector = 0x0e6a1faf)]
+        pub fn is_frozen(&self, account: AccountId) -> bool {
+            self.frozen.get(account).unwrap_or(false)
+        }
+
+        #[ink(message, selector = 0xc265d5b2)]
+        pub fn admins(&self) -> Vec<AccountId> {
+            self.admins.clone()
+        }
+
+        #[ink(message, selector = 0x36d37def)]
+        pub fn threshold(&self) -> u8 {
+            self.threshold
+        }
+
+        /// Returns the hash `propose_action` files `action` under.
+        #[ink(message, selector = 0x4ac08724)]
+        pub fn action_hash(&self, action: AdminAction) -> [u8; 32] {
+            self.env().hash_encoded::<Blake2x256, _>(&action)
+        }
+
+        #[ink(message, selector = 0x29199e31)]
+        pub fn confirmations_of(&self, action_hash: [u8; 32]) -> Vec<AccountId> {
+            self.confirmations.get(action_hash).unwrap_or_default()
+        }
+
+        #[ink(message, selector = 0x8d194a68)]
+        pub fn has_role(&self, role: RoleId, account: AccountId) -> bool {
+            self.roles.get((role, account)).unwrap_or(false)
+        }
+
+        /// Returns the role whose holders may grant and revoke `role`.
+        #[ink(message, selector = 0x553f5414)]
+        pub fn get_role_admin(&self, _role: RoleId) -> RoleId {
+            DEFAULT_ADMIN_ROLE
+        }
+
+        #[ink(message, selector = 0xfa7d505b)]
+        pub fn is_paused(&self) -> bool {
+            self.paused
+        }
+
+        #[ink(message, selector = 0x6e429d8d)]
+        pub fn pending_owner(&self) -> Option<AccountId> {
+            self.pending_owner
+        }
+
+        /// Nominates `new_owner`, who becomes the owner once it calls
+        /// `accept_ownership`.
+        ///
+        /// A later nomination replaces an earlier one.
+        #[ink(message, selector = 0x107e33ea)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.pending_owner = Some(new_owner);
+
+            Self::env().emit_event(
+                OwnershipTransferStarted {
+                    previous: self.owner,
+                    new: new_owner
+                }
+            );
+
+            Ok(())
+        }
+
+        #[ink(message, selector = 0xb55be9f0)]
+        pub fn accept_ownership(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            if self.pending_owner != Some(caller) {
+                return Err(Error::NotPendingOwner);
+            }
+            self.pending_owner = None;
+            self.set_owner(caller);
+            Ok(())
+        }
+
+        /// Leaves the contract without an owner, disabling every owner-only
+        /// message for good.
+        #[ink(message, selector = 0x8c90065b)]
+        pub fn renounce_ownership(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            self.pending_owner = None;
+            self.set_owner(AccountId::from([0u8; 32]));
+            Ok(())
+        }
+
+        #[ink(message, selector = 0x681266a0)]
+        pub fn approve(&mut self, spender: AccountId, value: Balance) -> Result<()>{
+            PSP22::approve(self, spender, value)
+        }
+
+        #[ink(message, selector = 0xf551d422)]
+        pub fn increase_allowance(&mut self, spender: AccountId, delta: Balance) -> Result<()> {
+            PSP22::increase_allowance(self, spender, delta)
+        }
+
+        #[ink(message, selector = 0xf998ebd9)]
+        pub fn decrease_allowance(&mut self, spender: AccountId, delta: Balance) -> Result<()> {
+            PSP22::decrease_allowance(self, spender, delta)
+        }
+
+        #[ink(message, selector = 0x84a15da1)]
+        pub fn transfer(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            PSP22::transfer(self, to, value, Vec::new())
+        }
+
+        #[ink(message, selector = 0x0b396f18)]
+        pub fn transfer_from(&mut self, from: AccountId, to: AccountId, value: Balance) -> Result<()> {
+            PSP22::transfer_from(self, from, to, value, Vec::new())
+        }
+
+        /// Like `transfer`, but returns the caller's balance afterwards.
+        #[ink(message, selector = 0xd2b58937)]
+        pub fn transfer_returning(&mut self, to: AccountId, value: Balance) -> Result<Balance> {
+            self.transfer(to, value)?;
+            Ok(self.balance_of(self.env().caller()))
+        }
+
+        /// Like `transfer`, but carried out at most once per caller and `nonce`.
+        ///
+        /// Retrying with a nonce that already went through succeeds without
+        /// moving tokens again. A failed transfer does not use up its nonce.
+        #[ink(message, selector = 0x301ed656)]
+        pub fn transfer_once(&mut self, to: AccountId, value: Balance, nonce: u64) -> Result<()> {
+            let caller = self.env().caller();
+            if self.used_transfer_nonces.get((caller, nonce)).unwrap_or(false) {
+                return Ok(());
+            }
+            self.transfer(to, value)?;
+            self.used_transfer_nonces.insert((caller, nonce), &true);
+            Ok(())
+        }
+
+        /// Clears the allowance of `spender` over the caller's tokens, removing
+        /// its storage entry instead of writing a zero.
+        ///
+        /// Works while the contract is paused or the caller frozen, so that
+        /// approvals can always be withdrawn.
+        #[ink(message, selector = 0xaae739d3)]
+        pub fn revoke_allowance(&mut self, spender: AccountId) -> Result<()> {
+            let owner = self.env().caller();
+            self.allowances.remove((owner, spender));
+            self.allowance_expiry.remove((owner, spender));
+            self.periodic_allowances.remove((owner, spender));
+            self.remove_allowance_pair(owner, spender);
+            self.remove_spender(owner, spender);
+            self.record_approval(owner, spender, 0);
+
+            Self::env().emit_event(
+                Approval {
+                    owner,
+                    spender,
+                    value: 0
+                }
+            );
+
+            Ok(())
+        }
+
+        /// Sets the allowance of `spender` to `new_value`, but only if it still
+        /// equals `current_expected`.
+        ///
+        /// Protects against a spender using the old allowance right before it
+        /// is replaced.
+        #[ink(message, selector = 0xafccdf14)]
+        pub fn safe_approve(
+            &mut self,
+            spender: AccountId,
+            current_expected: Balance,
+            new_value: Balance,
+        ) -> Result<()> {
+            let owner = self.env().caller();
+            if self.allowance(owner, spender) != current_expected {
+                return Err(Error::AllowanceChanged);
+            }
+            self.inter_approve(owner, spender, new_value)
+        }
+
+        /// Like `approve`, but the allowance reads as zero once the block
+        /// timestamp is past `deadline`.
+        #[ink(message, selector = 0xdd0fcf68)]
+        pub fn approve_with_expiry(&mut self, spender: AccountId, value: Balance, deadline: u64) -> Result<()> {
+            let owner = self.env().caller();
+            self.inter_approve(owner, spender, value)?;
+            self.allowance_expiry.insert((owner, spender), &deadline);
+            Ok(())
+        }
+
+        /// Sets the allowance of `spender` over the tokens of `owner` as if
+        /// `owner` had called `approve`, authorized by an ECDSA `signature`.
+        ///
+        /// The signed message is the BLAKE2 hash of `permit_hash` and includes
+        /// the owner's current nonce, so every signature can only be used once.
+        #[ink(message, selector = 0x84aff499)]
+        pub fn permit(
+            &mut self,
+            owner: AccountId,
+            spender: AccountId,
+            value: Balance,
+            deadline: u64,
+            signature: [u8; 65],
+        ) -> Result<()> {
+            if self.block_timestamp() > deadline {
+                return Err(Error::PermitExpired);
+            }
+
+            let nonce = self.nonce_of(owner);
+            let message_hash = self.permit_hash(owner, spender, value, nonce, deadline);
+            let public_key = self.env()
+                .ecdsa_recover(&signature, &message_hash)
+                .map_err(|_| Error::InvalidSignature)?;
+            let signer = AccountId::from(self.env().hash_bytes::<Blake2x256>(&public_key));
+            if signer != owner {
+                return Err(Error::InvalidSignature);
+            }
+
+            self.inter_approve(owner, spender, value)?;
+            self.nonces.insert(owner, &(nonce + 1));
+
+            Ok(())
+        }
+
+        /// Lets `spender` pull up to `per_period_limit` of the caller's tokens
+        /// through `transfer_from` in every period of `period_seconds`.
+        ///
+        /// A period starts with the first pull after the previous one ended.
+        /// `burn_from` counts against the limit as well. Any later `approve`
+        /// of the same spender replaces the periodic limit, while
+        /// `increase_allowance` and `decrease_allowance` are rejected.
+        #[ink(message, selector = 0x256f0b87)]
+        pub fn approve_periodic(
+            &mut self,
+            spender: AccountId,
+            per_period_limit: Balance,
+            period_seconds: u64,
+        ) -> Result<()> {
+            let owner = self.env().caller();
+            self.inter_approve(owner, spender, per_period_limit)?;
+            self.periodic_allowances.insert(
+                (owner, spender),
+                &(per_period_limit, period_seconds, 0, self.block_timestamp()),
+            );
+            Ok(())
+        }
+
+        /// Redeems a `permit` of `owner` naming the caller as spender and spends
+        /// it right away on a transfer of `value` tokens to `to`.
+        ///
+        /// A bad permit fails with `PermitExpired` or `InvalidSignature`
+        /// before any tokens move; every other error comes from the transfer.
+        #[ink(message, selector = 0x6fc4f210)]
+        pub fn transfer_from_with_permit(
+            &mut self,
+            owner: AccountId,
+            to: AccountId,
+            value: Balance,
+            deadline: u64,
+            signature: [u8; 65],
+        ) -> Result<()> {
+            let caller = self.env().caller();
+            self.permit(owner, caller, value, deadline, signature)?;
+            self.transfer_from(owner, to, value)
+        }
+
+        /// Transfers `value` tokens from the caller to `to`, attaching an
+        /// arbitrary `data` payload that is passed on to the transfer hook.
+        #[ink(message, selector = 0x454fc2c7)]
+        pub fn transfer_with_data(&mut self, to: AccountId, value: Balance, data: Vec<u8>) -> Result<()> {
+            let from = self.env().caller();
+            let fee_bps = self.current_fee_bps();
+            self.inter_transfer(from, to, value, fee_bps)?;
+
+            Self::env().emit_event(
+                TransferWithData {
+                    from: Some(from),
+                    to: Some(to),
+                    value,
+                    data
+                }
+            );
+
+            Ok(())
+        }
+
+        /// Delegates the caller's voting power to `delegatee`.
+        ///
+        /// Votes follow the caller's balance from then on.
+        #[ink(message, selector = 0xc59654fe)]
+        pub fn delegate(&mut self, delegatee: AccountId) {
+            let delegator = self.env().caller();
+            self.inter_delegate(delegator, delegatee);
+        }
+
+        /// Transfers `value` tokens from the caller to `to` and, if `to` is a
+        /// contract, notifies it through `TokenReceiver::on_token_received`.
+        ///
+        /// Fails with `TransferCallbackFailed`, reverting the transfer, if the
+        /// callback traps or does not return `ON_TOKEN_RECEIVED_SELECTOR`.
+        #[ink(message, selector = 0xacd10e50)]
+        pub fn transfer_and_call(&mut self, to: AccountId, value: Balance, data: Vec<u8>) -> Result<()> {
+            let from = self.env().caller();
+            let fee_bps = self.current_fee_bps();
+            self.non_reentrant(|contract| {
+                contract.inter_transfer(from, to, value, fee_bps)?;
+                contract.notify_receiver(from, from, to, value, data)
+            })
+        }
+
+        /// Lends `amount` freshly minted tokens to `receiver` for the duration
+        /// of its `FlashBorrower::on_flash_loan` callback.
+        ///
+        /// Afterwards `amount` is burned from `receiver` and the fee is moved
+        /// to the owner, failing with `FlashLoanNotRepaid` if `receiver` holds
+        /// less than `amount + fee` at that point. The fee is exactly `flash_fee(amount)`: transfer fees,
+        /// burns and transfer limits do not apply to it.
+        ///
+        /// The callback is made with the default call flags, which deny
+        /// re-entry, so the borrower cannot move this token from within
+        /// `on_flash_loan`; it has to hold `amount + fee` when it returns.
+        #[ink(message, selector = 0xf86c41f3)]
+        pub fn flash_mint(&mut self, receiver: AccountId, amount: Balance, data: Vec<u8>) -> Result<()> {
+            let initiator = self.env().caller();
+            let fee = self.apply_bps(amount, self.flash_fee_bps);
+            let repayment = amount.checked_add(fee).ok_or(Error::Overflow)?;
+
+            self.non_reentrant(|contract| {
+                contract.inter_mint(receiver, amount)?;
+                contract.call_flash_borrower(initiator, receiver, amount, fee, data)?;
+
+                if contract.balance_of(receiver) < repayment {
+                    return Err(Error::FlashLoanNotRepaid);
+                }
+                contract.inter_burn(receiver, amount)?;
+                if fee > 0 {
+                    contract.move_balance(receiver, contract.owner, fee)?;
+                }
+
+                Ok(())
+            })
+        }
+
+        /// Mints one base unit for every `price_per_token` of native currency
+        /// sent along with the call and refunds the remainder.
+        #[ink(message, payable, selector = 0x15d62801)]
+        pub fn buy(&mut self) -> Result<()> {
+            if !self.sale_active || self.price_per_token == 0 {
+                return Err(Error::SaleInactive);
+            }
+            let buyer = self.env().caller();
+            let paid = self.env().transferred_value();
+            let amount = paid / self.price_per_token;
+            let refund = paid % self.price_per_token;
+            self.inter_mint(buyer, amount)?;
+            if refund > 0 {
+                self.env()
+                    .transfer(buyer, refund)
+                    .map_err(|_| Error::NativeTransferFailed)?;
+            }
+            Ok(())
+        }
+
+        /// Mints one token for every unit of native currency sent along with the call.
+        #[ink(message, payable, selector = 0x2d10c9bd)]
+        pub fn deposit(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            let value = self.env().transferred_value();
+            let wrapped_native = self.wrapped_native.checked_add(value).ok_or(Error::Overflow)?;
+            let deposited = self.native_deposit(caller).checked_add(value).ok_or(Error::Overflow)?;
+            self.inter_mint(caller, value)?;
+            self.wrapped_native = wrapped_native;
+            self.native_deposits.insert(caller, &deposited);
+            Ok(())
+        }
+
+        /// Burns `value` tokens of the caller and pays out the same amount of
+        /// native currency.
+        ///
+        /// Only native currency the caller deposited can be withdrawn, so that
+        /// minted or earned tokens cannot take the native currency held for
+        /// dividends or escrowed withdrawals; fails with `ExceedsDeposit`
+        /// otherwise. Tokens received by transfer carry no deposit along.
+        ///
+        /// If the payout fails, the amount is kept for the caller to collect
+        /// with `claim_native` instead of reverting the burn.
+        #[ink(message, selector = 0x410fcc9d)]
+        pub fn withdraw(&mut self, value: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            let deposited = self.native_deposit(caller);
+            if value > deposited {
+                return Err(Error::ExceedsDeposit);
+            }
+            self.inter_burn(caller, value)?;
+            // Lowered before the payout, so that the backing cannot be
+            // withdrawn twice.
+            self.native_deposits.insert(caller, &(deposited - value));
+            self.wrapped_native -= value;
+            if !self.pay_native(caller, value) {
+                let pending = self.pending_withdrawal(caller);
+                let new_pending = pending.checked_add(value).ok_or(Error::Overflow)?;
+                self.pending_withdrawals.insert(caller, &new_pending);
+
+                Self::env().emit_event(
+                    WithdrawalEscrowed {
+                        account: caller,
+                        amount: value
+                    }
+                );
+            }
+            Ok(())
+        }
+
+        /// Withdraws as much of the caller's deposit as its balance covers,
+        /// escrowing it like `withdraw` if the payout fails.
+        ///
+        /// The balance and supply are reduced before the payout, so a
+        /// recipient calling back in finds nothing left to withdraw.
+        #[ink(message, selector = 0x85bb29a8)]
+        pub fn withdraw_all(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            let value = self.balance_of(caller).min(self.native_deposit(caller));
+            if value == 0 {
+                return Ok(());
+            }
+            self.withdraw(value)
+        }
+
+        /// Pays out the native currency a failed `withdraw` kept for the caller.
+        #[ink(message, selector = 0x0cc3778a)]
+        pub fn claim_native(&mut self) -> Result<()> {
+            let account = self.env().caller();
+            let amount = self.pending_withdrawal(account);
+            if amount == 0 {
+                return Ok(());
+            }
+            // A native transfer runs no code on the recipient's side, so the
+            // entry can be cleared after it went through.
+            self.transfer_native(account, amount)?;
+            self.pending_withdrawals.remove(account);
+
+            Self::env().emit_event(WithdrawalClaimed { account, amount });
+
+            Ok(())
+        }
+
+        /// Queues a transfer of `value` tokens from the caller to `to` that
+        /// `execute_queued` carries out once the transfer delay has passed.
+        ///
+        /// Returns the id of the queued transfer. The tokens stay with the
+        /// caller until then.
+        #[ink(message, selector = 0x37cedba0)]
+        pub fn queue_transfer(&mut self, to: AccountId, value: Balance) -> Result<u64> {
+            let from = self.env().caller();
+            let ready_at = self
+                .block_timestamp()
+                .checked_add(self.transfer_delay)
+                .ok_or(Error::Overflow)?;
+            let id = self.next_queued_id;
+            self.next_queued_id += 1;
+            self.queued_transfers.insert(id, &QueuedTransfer { from, to, value, ready_at });
+
+            Self::env().emit_event(
+                TransferQueued {
+                    id,
+                    from,
+                    to,
+                    value,
+                    ready_at
+                }
+            );
+
+            Ok(id)
+        }
+
+        /// Carries out the queued transfer `id`; anyone may call it once the
+        /// transfer is ready.
+        #[ink(message, selector = 0xd5b3be12)]
+        pub fn execute_queued(&mut self, id: u64) -> Result<()> {
+            let queued = self.queued_transfers.get(id).ok_or(Error::UnknownQueuedTransfer)?;
+            if self.block_timestamp() < queued.ready_at {
+                return Err(Error::TimelockNotElapsed);
+            }
+            self.queued_transfers.remove(id);
+            let fee_bps = self.current_fee_bps();
+            self.inter_transfer_unlocked(queued.from, queued.to, queued.value, fee_bps)?;
+
+            Self::env().emit_event(TransferExecuted { id });
+
+            Ok(())
+        }
+
+        /// Releases the caller's vested tokens.
+        #[ink(message, selector = 0x3f2be152)]
+        pub fn release(&mut self) -> Result<()> {
+            let beneficiary = self.env().caller();
+            let value = self.releasable(beneficiary);
+            let mut vesting = match self.vestings.get(beneficiary) {
+                Some(vesting) if value > 0 => vesting,
+                _ => return Ok(()),
+            };
+            // The tokens leave the contract's escrow rather than a holder, so
+            // only the pause and the beneficiary's freeze apply.
+            self._before_token_transfer(None, Some(beneficiary), value)?;
+            vesting.released += value;
+            self.vestings.insert(beneficiary, &vesting);
+            self.move_balance(self.env().account_id(), beneficiary, value)?;
+
+            Self::env().emit_event(
+                TokensReleased {
+                    beneficiary,
+                    value
+                }
+            );
+
+            Ok(())
+        }
+
+        /// Locks `amount` of the caller's tokens in the contract, earning
+        /// `reward_rate / REWARD_PRECISION` tokens per staked token and block.
+        #[ink(message, selector = 0x5adb38de)]
+        pub fn stake(&mut self, amount: Balance) -> Result<()> {
+            let account = self.env().caller();
+            self.ensure_not_paused()?;
+            self.ensure_not_frozen(account)?;
+            let staked = self.settle_rewards(account)?;
+            let new_staked = staked.checked_add(amount).ok_or(Error::Overflow)?;
+            self.move_balance(account, self.env().account_id(), amount)?;
+            self.staked.insert(account, &(new_staked, self.reward_per_token));
+
+            Self::env().emit_event(Staked { account, amount });
+
+            Ok(())
+        }
+
+        /// Returns `amount` staked tokens to the caller; accrued rewards stay
+        /// claimable.
+        #[ink(message, selector = 0x82364901)]
+        pub fn unstake(&mut self, amount: Balance) -> Result<()> {
+            let account = self.env().caller();
+            self.ensure_not_paused()?;
+            let staked = self.settle_rewards(account)?;
+            let new_staked = staked.checked_sub(amount).ok_or(Error::InsufficientStake)?;
+            self.move_balance(self.env().account_id(), account, amount)?;
+            self.staked.insert(account, &(new_staked, self.reward_per_token));
+
+            Self::env().emit_event(Unstaked { account, amount });
+
+            Ok(())
+        }
+
+        /// Mints the caller's pending staking rewards to the caller.
+        #[ink(message, selector = 0x8027650c)]
+        pub fn claim_rewards(&mut self) -> Result<()> {
+            let account = self.env().caller();
+            let staked = self.settle_rewards(account)?;
+            self.staked.insert(account, &(staked, self.reward_per_token));
+            let reward = self.accrued_rewards.get(account).unwrap_or(0);
+            if reward == 0 {
+                return Ok(());
+            }
+            self.accrued_rewards.insert(account, &0);
+            self.inter_mint(account, reward)?;
+
+            Self::env().emit_event(RewardPaid { account, reward });
+
+            Ok(())
+        }
+
+        /// Mints `emission_per_block` tokens to the emission recipient for
+        /// every block since the last emission; anyone may trigger it.
+        #[ink(message, selector = 0xcc33f411)]
+        pub fn mint_emission(&mut self) -> Result<()> {
+            let current_block = self.env().block_number();
+            let elapsed = current_block.saturating_sub(self.last_emission_block);
+            if elapsed == 0 {
+                return Ok(());
+            }
+            let emission = self.emission_per_block
+                .checked_mul(elapsed as Balance)
+                .ok_or(Error::Overflow)?;
+            if emission > 0 {
+                self.inter_mint(self.emission_recipient, emission)?;
+            }
+            self.last_emission_block = current_block;
+            Ok(())
+        }
+
+        /// Accepts native currency without minting anything in return.
+        #[ink(message, payable, selector = 0x4aafa343)]
+        pub fn fund(&mut self) {
+            Self::env().emit_event(
+                Funded {
+                    from: self.env().caller(),
+                    amount: self.env().transferred_value()
+                }
+            );
+        }
+
+        /// Shares the native currency sent along with the call among all
+        /// holders in proportion to their current balances.
+        ///
+        /// Holders collect their share with `claim_dividends`; the rounding
+        /// remainder stays with the contract.
+        #[ink(message, payable, selector = 0x4f1a05ed)]
+        pub fn distribute(&mut self) -> Result<()> {
+            let amount = self.env().transferred_value();
+            let total_supply = self.total_supply;
+            if total_supply == 0 {
+                return Err(Error::NoSupply);
+            }
+            let per_share = amount
+                .checked_mul(DIVIDEND_PRECISION)
+                .ok_or(Error::Overflow)?
+                / total_supply;
+            self.dividend_per_share = self.dividend_per_share
+                .checked_add(per_share)
+                .ok_or(Error::Overflow)?;
+
+            Self::env().emit_event(
+                DividendDistributed {
+                    from: self.env().caller(),
+                    amount
+                }
+            );
+
+            Ok(())
+        }
+
+        /// Pays the caller's unclaimed dividends out in native currency.
+        #[ink(message, selector = 0x1958ed23)]
+        pub fn claim_dividends(&mut self) -> Result<()> {
+            let account = self.env().caller();
+            self.settle_dividends(account, self.balance_of(account));
+            let amount = self.unclaimed_dividends.get(account).unwrap_or(0);
+            if amount == 0 {
+                return Ok(());
+            }
+            let withdrawn = self.withdrawn_dividends.get(account).unwrap_or(0);
+            let new_withdrawn = withdrawn.checked_add(amount).ok_or(Error::Overflow)?;
+            self.unclaimed_dividends.remove(account);
+            self.withdrawn_dividends.insert(account, &new_withdrawn);
+            self.env()
+                .transfer(account, amount)
+                .map_err(|_| Error::NativeTransferFailed)?;
+
+            Self::env().emit_event(DividendClaimed { account, amount });
+
+            Ok(())
+        }
+
+        /// Opens a proposal for `VOTING_PERIOD` blocks and returns its id.
+        ///
+        /// `description_hash` identifies the off-chain description and
+        /// `call_data` the call to make once the proposal passes.
+        #[ink(message, selector = 0xcb14b5b5)]
+        pub fn propose(&mut self, description_hash: [u8; 32], call_data: Vec<u8>) -> u64 {
+            let proposer = self.env().caller();
+            let start_block = self.env().block_number();
+            let end_block = start_block.saturating_add(VOTING_PERIOD);
+            let quorum = self.total_supply().saturating_mul(QUORUM_BPS as Balance) / 10_000;
+            let proposal_id = self.next_proposal_id;
+            self.next_proposal_id += 1;
+            self.proposals.insert(
+                proposal_id,
+                &Proposal {
+                    proposer,
+                    description_hash,
+                    call_data,
+                    start_block,
+                    end_block,
+                    quorum,
+                    for_votes: 0,
+                    against_votes: 0,
+                    executed: false,
+                },
+            );
+
+            Self::env().emit_event(
+                ProposalCreated {
+                    proposal_id,
+                    proposer,
+                    description_hash,
+                    end_block
+                }
+            );
+
+            proposal_id
+        }
+
+        /// Votes for or against a proposal with the caller's voting power at
+        /// the end of the block before the proposal was created.
+        ///
+        /// The creation block is still open, so votes moved within it would
+        /// count twice; a proposal created in the first block has no voters.
+        #[ink(message, selector = 0xcaed155c)]
+        pub fn cast_vote(&mut self, proposal_id: u64, support: bool) -> Result<()> {
+            let voter = self.env().caller();
+            let mut proposal = self.proposals.get(proposal_id).ok_or(Error::UnknownProposal)?;
+            if self.env().block_number() > proposal.end_block {
+                return Err(Error::VotingClosed);
+            }
+            if self.has_voted(proposal_id, voter) {
+                return Err(Error::AlreadyVoted);
+            }
+            let weight = proposal
+                .start_block
+                .checked_sub(1)
+                .map_or(0, |block| self.get_past_votes(voter, block));
+            if weight == 0 {
+                return Err(Error::NoVotingPower);
+            }
+            if support {
+                proposal.for_votes = proposal.for_votes.checked_add(weight).ok_or(Error::Overflow)?;
+            } else {
+                proposal.against_votes = proposal.against_votes.checked_add(weight).ok_or(Error::Overflow)?;
+            }
+            self.proposals.insert(proposal_id, &proposal);
+            self.proposal_votes.insert((proposal_id, voter), &true);
+
+            Self::env().emit_event(
+                VoteCast {
+                    proposal_id,
+                    voter,
+                    support,
+                    weight
+                }
+            );
+
+            Ok(())
+        }
+
+        /// Marks a passed proposal as executed once its voting period ended.
+        ///
+        /// The contract does not dispatch `call_data` itself; it is emitted
+        /// with `ProposalExecuted` for the executor to carry out.
+        #[ink(message, selector = 0xd00ec894)]
+        pub fn execute(&mut self, proposal_id: u64) -> Result<()> {
+            let mut proposal = self.proposals.get(proposal_id).ok_or(Error::UnknownProposal)?;
+            if proposal.executed {
+                return Err(Error::ProposalAlreadyExecuted);
+            }
+            if self.env().block_number() <= proposal.end_block {
+                return Err(Error::VotingActive);
+            }
+            if proposal.for_votes.saturating_add(proposal.against_votes) < proposal.quorum {
+                return Err(Error::QuorumNotReached);
+            }
+            if proposal.for_votes <= proposal.against_votes {
+                return Err(Error::ProposalDefeated);
+            }
+            proposal.executed = true;
+            self.proposals.insert(proposal_id, &proposal);
+
+            Self::env().emit_event(
+                ProposalExecuted {
+                    proposal_id,
+                    call_data: proposal.call_data
+                }
+            );
+
+            Ok(())
+        }
+
+        /// Burns `amount` of the caller's tokens so that a relayer can
+        /// release them to `dest_account` on chain `dest_chain`.
+        #[ink(message, selector = 0xbbaa6540)]
+        pub fn lock(&mut self, amount: Balance, dest_chain: u32, dest_account: Vec<u8>) -> Result<()> {
+            let from = self.env().caller();
+            self.inter_burn(from, amount)?;
+
+            Self::env().emit_event(
+                Locked {
+                    from,
+                    amount,
+                    dest_chain,
+                    dest_account
+                }
+            );
+
+            Ok(())
+        }
+
+        /// Mints `amount` tokens to `to` for tokens locked on another chain.
+        ///
+        /// Every `source_nonce` can be used once, so a lock cannot be
+        /// relayed twice.
+        #[ink(message, selector = 0xaa9ea9de)]
+        pub fn unlock(&mut self, to: AccountId, amount: Balance, source_nonce: u64) -> Result<()> {
+            self.ensure_role(RELAYER_ROLE)?;
+            if self.used_source_nonces.get(source_nonce).unwrap_or(false) {
+                return Err(Error::NonceAlreadyUsed);
+            }
+            self.inter_mint(to, amount)?;
+            self.used_source_nonces.insert(source_nonce, &true);
+
+            Self::env().emit_event(
+                Unlocked {
+                    to,
+                    amount,
+                    source_nonce
+                }
+            );
+
+            Ok(())
+        }
+
+        /// Transfers tokens from the caller to every `(to, value)` pair.
+        ///
+        /// The batch is all-or-nothing: the summed amount is checked against the
+        /// caller's balance before any transfer is applied.
+        #[ink(message, selector = 0xc7a9a616)]
+        pub fn batch_transfer(&mut self, recipients: Vec<(AccountId, Balance)>) -> Result<()> {
+            if recipients.len() > MAX_BATCH_LEN {
+                return Err(Error::BatchTooLarge);
+            }
+
+            let from = self.env().caller();
+            let total = recipients
+                .iter()
+                .try_fold(0 as Balance, |acc, (_, value)| acc.checked_add(*value))
+                .ok_or(Error::Overflow)?;
+
+            if total > self.balance_of(from) {
+                return Err(Error::InsufficientBalance);
+            }
+
+            // Every leg pays the same fee, with a single oracle call.
+            let fee_bps = self.current_fee_bps();
+            for (to, value) in recipients {
+                self.inter_transfer(from, to, value, fee_bps)?;
+            }
+
+            Ok(())
+        }
+
+        /// Sets the caller's allowance for every `(spender, value)` pair.
+        #[ink(message, selector = 0xddce9941)]
+        pub fn batch_approve(&mut self, approvals: Vec<(AccountId, Balance)>) -> Result<()> {
+            if approvals.len() > MAX_BATCH_LEN {
+                return Err(Error::BatchTooLarge);
+            }
+
+            let owner = self.env().caller();
+            for (spender, value) in approvals {
+                self.inter_approve(owner, spender, value)?;
+            }
+
+            Ok(())
+        }
+
+        // Permissionless supply reduction over the caller's own tokens or allowance.
+
+        #[ink(message, selector = 0xb1efc17b)]
+        pub fn burn(&mut self, value: Balance) -> Result<()> {
+            let from = self.env().caller();
+            self.inter_burn(from, value)
+        }
+
+        /// Burns `value` tokens of `from`, spending the caller's allowance as
+        /// `transfer_from` does, periodic limits included.
+        #[ink(message, selector = 0x27212bbb)]
+        pub fn burn_from(&mut self, from: AccountId, value: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            let periodic = self.spend_periodic_allowance(from, caller, value)?;
+            if periodic.is_none() {
+                self.ensure_allowance(from, caller, value)?;
+            }
+            self.inter_burn(from, value)?;
+
+            match periodic {
+                Some(periodic) => {
+                    self.periodic_allowances.insert((from, caller), &periodic);
+                    Ok(())
+                }
+                None => self.spend_allowance(from, caller, value),
+            }
+        }
+
+        // Permissioned supply management, only callable by the owner or role holders.
+
+        #[ink(message, selector = 0xcfdd9aa2)]
+        pub fn mint(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            self.ensure_role(MINTER_ROLE)?;
+            self.ensure_no_multisig()?;
+            self.rate_limited_mint(ink_prelude::vec![(to, value)])
+        }
+
+        /// Mints to every `(to, value)` pair.
+        ///
+        /// The batch is all-or-nothing: the summed amount is checked against the
+        /// cap before anything is minted.
+        #[ink(message, selector = 0x7e4353fc)]
+        pub fn mint_batch(&mut self, recipients: Vec<(AccountId, Balance)>) -> Result<()> {
+            self.ensure_role(MINTER_ROLE)?;
+            self.ensure_no_multisig()?;
+            if recipients.len() > MAX_BATCH_LEN {
+                return Err(Error::BatchTooLarge);
+            }
+            self.rate_limited_mint(recipients)
+        }
+
+        #[ink(message, selector = 0x1e64ef3d)]
+        pub fn burn_from_any(&mut self, from: AccountId, value: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            self.inter_burn(from, value)
+        }
+
+        #[ink(message, selector = 0x81e0c604)]
+        pub fn pause(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            self.ensure_no_multisig()?;
+            self.paused = true;
+
+            Self::env().emit_event(
+                Paused {
+                    account: self.env().caller()
+                }
+            );
+
+            Ok(())
+        }
+
+        #[ink(message, selector = 0x67616649)]
+        pub fn unpause(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            self.ensure_no_multisig()?;
+            self.paused = false;
+
+            Self::env().emit_event(
+                Unpaused {
+                    account: self.env().caller()
+                }
+            );
+
+            Ok(())
+        }
+
+        /// Records the current balances and total supply under a new snapshot id.
+        #[ink(message, selector = 0x798ada01)]
+        pub fn snapshot(&mut self) -> Result<u64> {
+            self.ensure_owner()?;
+            self.current_snapshot_id += 1;
+            let id = self.current_snapshot_id;
+
+            Self::env().emit_event(Snapshot { id });
+
+            Ok(id)
+        }
+
+        #[ink(message, selector = 0xd3f73e00)]
+        pub fn set_fee_bps(&mut self, fee_bps: u16) -> Result<()> {
+            self.ensure_owner()?;
+            if fee_bps as u32 + self.burn_bps as u32 > 10_000 {
+                return Err(Error::InvalidBasisPoints);
+            }
+            if fee_bps > 0 && self.treasury.is_none() {
+                return Err(Error::NoTreasury);
+            }
+            self.fee_bps = fee_bps;
+            Ok(())
+        }
+
+        /// Sets the account that receives transfer fees and swept dust; it
+        /// cannot be the zero account, where the tokens would be lost.
+        #[ink(message, selector = 0xe6812781)]
+        pub fn set_treasury(&mut self, treasury: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            if treasury == AccountId::from([0u8; 32]) {
+                return Err(Error::ZeroAddress);
+            }
+            self.treasury = Some(treasury);
+            Ok(())
+        }
+
+        /// Chooses how fees and burns are rounded; the default is `Down`.
+        #[ink(message, selector = 0x55c2ecc3)]
+        pub fn set_rounding_mode(&mut self, rounding_mode: RoundingMode) -> Result<()> {
+            self.ensure_owner()?;
+            self.rounding_mode = rounding_mode;
+            Ok(())
+        }
+
+        /// Lets the `FeeOracle` contract `oracle` set the transfer fee; `None`
+        /// goes back to the stored `fee_bps`.
+        ///
+        /// The stored fee still applies whenever the oracle call fails or
+        /// returns a fee that does not fit next to `burn_bps`.
+        #[ink(message, selector = 0xad28346f)]
+        pub fn set_oracle(&mut self, oracle: Option<AccountId>) -> Result<()> {
+            self.ensure_owner()?;
+            self.oracle = oracle;
+            Ok(())
+        }
+
+        /// Burns `burn_bps` basis points of every transfer.
+        ///
+        /// Together with the transfer fee it may take at most the whole value.
+        #[ink(message, selector = 0xa4ff7bef)]
+        pub fn set_burn_bps(&mut self, burn_bps: u16) -> Result<()> {
+            self.ensure_owner()?;
+            if self.fee_bps as u32 + burn_bps as u32 > 10_000 {
+                return Err(Error::InvalidBasisPoints);
+            }
+            self.burn_bps = burn_bps;
+            Ok(())
+        }
+
+        /// Blocks every transfer from or to `account` and every approval it gives.
+        #[ink(message, selector = 0xb5fb2de4)]
+        pub fn freeze(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.frozen.insert(account, &true);
+
+            Self::env().emit_event(AccountFrozen { account });
+
+            Ok(())
+        }
+
+        #[ink(message, selector = 0x40715f40)]
+        pub fn unfreeze(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.frozen.insert(account, &false);
+
+            Self::env().emit_event(AccountUnfrozen { account });
+
+            Ok(())
+        }
+
+        #[ink(message, selector = 0x2aabfab5)]
+        pub fn grant_role(&mut self, role: RoleId, account: AccountId) -> Result<()> {
+            self.ensure_role(self.get_role_admin(role))?;
+            self.roles.insert((role, account), &true);
+
+            Self::env().emit_event(
+                RoleGranted {
+                    role,
+                    account,
+                    sender: self.env().caller()
+                }
+            );
+
+            Ok(())
+        }
+
+        #[ink(message, selector = 0x35e1ef4a)]
+        pub fn revoke_role(&mut self, role: RoleId, account: AccountId) -> Result<()> {
+            self.ensure_role(self.get_role_admin(role))?;
+            self.roles.insert((role, account), &false);
+
+            Self::env().emit_event(
+                RoleRevoked {
+                    role,
+                    account,
+                    sender: self.env().caller()
+                }
+            );
+
+            Ok(())
+        }
+
+        /// Moves `amount` of the foreign PSP22 `token` held by this contract to `to`.
+        #[ink(message, selector = 0x62304193)]
+        pub fn recover_tokens(&mut self, token: AccountId, to: AccountId, amount: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            if token == self.env().account_id() {
+                return Err(Error::CannotRecoverSelf);
+            }
+            self.non_reentrant(|contract| contract.call_token_transfer(token, to, amount))
+        }
+
+        /// Sends `amount` of the contract's native currency, or of the foreign
+        /// PSP22 `token`, to `to`.
+        ///
+        /// A last resort for stuck funds, so it ignores the pause and every
+        /// limit; it only checks that the contract holds `amount`.
+        #[ink(message, selector = 0xd7ee888f)]
+        pub fn emergency_withdraw(&mut self, token: Option<AccountId>, to: AccountId, amount: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            match token {
+                None => {
+                    if amount > self.env().balance() {
+                        return Err(Error::InsufficientContractBalance);
+                    }
+                    self.env()
+                        .transfer(to, amount)
+                        .map_err(|_| Error::NativeTransferFailed)?;
+                }
+                Some(token) => {
+                    if token == self.env().account_id() {
+                        return Err(Error::CannotRecoverSelf);
+                    }
+                    let held = self.call_token_balance_of(token, self.env().account_id())?;
+                    if amount > held {
+                        return Err(Error::InsufficientContractBalance);
+                    }
+                    self.non_reentrant(|contract| contract.call_token_transfer(token, to, amount))?;
+                }
+            }
+
+            Self::env().emit_event(
+                EmergencyWithdrawal {
+                    token,
+                    to,
+                    amount
+                }
+            );
+
+            Ok(())
+        }
+
+        /// Moves `value` tokens from `from` to `to` without an allowance.
+        ///
+        /// Meant for recovering funds of compromised accounts, so it bypasses
+        /// the pause and freeze checks as well as the transfer fee.
+        #[ink(message, selector = 0x66837d18)]
+        pub fn force_transfer(&mut self, from: AccountId, to: AccountId, value: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            self.ensure_not_zero_address(to)?;
+            self.move_balance(from, to, value)?;
+
+            Self::env().emit_event(
+                ForcedTransfer {
+                    by: self.env().caller(),
+                    from,
+                    to,
+                    value
+                }
+            );
+
+            Ok(())
+        }
+
+        /// Requires transfers above `threshold` to go through `queue_transfer`
+        /// and wait `delay` milliseconds; `None` turns the timelock off.
+        #[ink(message, selector = 0xf83d162e)]
+        pub fn set_timelock(&mut self, threshold: Option<Balance>, delay: u64) -> Result<()> {
+            self.ensure_owner()?;
+            self.large_transfer_threshold = threshold;
+            self.transfer_delay = delay;
+            Ok(())
+        }
+
+        /// Locks `total` of the owner's tokens in the contract and lets them vest
+        /// linearly to `beneficiary` over `duration` milliseconds from `start`.
+        #[ink(message, selector = 0xb471202d)]
+        pub fn create_vesting(
+            &mut self,
+            beneficiary: AccountId,
+            total: Balance,
+            start: u64,
+            duration: u64,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            if self.vestings.get(beneficiary).is_some() {
+                return Err(Error::VestingExists);
+            }
+            self.move_balance(self.owner, self.env().account_id(), total)?;
+            self.vestings.insert(beneficiary, &Vesting { total, released: 0, start, duration });
+
+            Self::env().emit_event(
+                VestingCreated {
+                    beneficiary,
+                    total,
+                    start,
+                    duration
+                }
+            );
+
+            Ok(())
+        }
+
+        #[ink(message, selector = 0x93f1e657)]
+        pub fn set_reward_rate(&mut self, reward_rate: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            // Rewards up to now are earned at the old rate.
+            self.update_reward_per_token();
+            self.reward_rate = reward_rate;
+            Ok(())
+        }
+
+        /// Moves the whole balance of every listed account holding less than
+        /// `threshold` tokens to the treasury.
+        #[ink(message, selector = 0x6b5c60be)]
+        pub fn sweep_dust(&mut self, accounts: Vec<AccountId>, threshold: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            let treasury = self.treasury.ok_or(Error::NoTreasury)?;
+            if accounts.len() > MAX_BATCH_LEN {
+                return Err(Error::BatchTooLarge);
+            }
+
+            for account in accounts {
+                let balance = self.balance_of(account);
+                if balance > 0 && balance < threshold {
+                    self.move_balance(account, treasury, balance)?;
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Leaves the balance of `account` out of `circulating_supply`.
+        #[ink(message, selector = 0x00e6484b)]
+        pub fn add_excluded(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            if self.excluded.len() >= MAX_BATCH_LEN {
+                return Err(Error::BatchTooLarge);
+            }
+            if !self.excluded.contains(&account) {
+                self.excluded.push(account);
+            }
+            Ok(())
+        }
+
+        #[ink(message, selector = 0x07472bfe)]
+        pub fn remove_excluded(&mut self, account: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.excluded.retain(|excluded| *excluded != account);
+            Ok(())
+        }
+
+        /// Replaces the code of this contract with the code stored under
+        /// `code_hash`, keeping all storage.
+        ///
+        /// The new code must use a storage layout compatible with this one:
+        /// fields may only be appended, never reordered, retyped or removed.
+        #[ink(message, selector = 0x9852f7b0)]
+        pub fn upgrade(&mut self, code_hash: [u8; 32]) -> Result<()> {
+            self.ensure_owner()?;
+            ink_env::set_code_hash(&code_hash).map_err(|_| Error::UpgradeFailed)?;
+
+            Self::env().emit_event(CodeUpgraded { code_hash });
+
+            Ok(())
+        }
+
+        /// Removes the contract and sends its native balance to `beneficiary`.
+        ///
+        /// Holders would lose their tokens, so this fails with
+        /// `SupplyOutstanding` while the total supply is nonzero unless
+        /// `force` is set.
+        #[ink(message, selector = 0x476d839f)]
+        pub fn terminate(&mut self, beneficiary: AccountId, force: bool) -> Result<()> {
+            self.ensure_owner()?;
+            if self.total_supply > 0 && !force {
+                return Err(Error::SupplyOutstanding);
+            }
+            self.env().terminate_contract(beneficiary)
+        }
+
+        /// Changes the emission rate after paying out what was emitted at the
+        /// old rate.
+        ///
+        /// Works even if that payout fails, so that emissions can always be
+        /// stopped; see `settle_emission`.
+        #[ink(message, selector = 0xf6ba0ddd)]
+        pub fn set_emission_per_block(&mut self, emission_per_block: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            self.settle_emission();
+            self.emission_per_block = emission_per_block;
+            Ok(())
+        }
+
+        /// Changes the emission recipient after paying the old one, like
+        /// `set_emission_per_block`.
+        #[ink(message, selector = 0xff91b287)]
+        pub fn set_emission_recipient(&mut self, recipient: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.settle_emission();
+            self.emission_recipient = recipient;
+            Ok(())
+        }
+
+        /// Limits the value of a single transfer; `None` lifts the limit.
+        ///
+        /// Transfers from or to the owner or the treasury are exempt.
+        #[ink(message, selector = 0xbbcb5f3f)]
+        pub fn set_max_tx_amount(&mut self, max_tx_amount: Option<Balance>) -> Result<()> {
+            self.ensure_owner()?;
+            self.max_tx_amount = max_tx_amount;
+            Ok(())
+        }
+
+        /// Opens or closes the sale through `buy` at `price_per_token` native
+        /// currency per base unit.
+        #[ink(message, selector = 0x8f30c986)]
+        pub fn set_sale(&mut self, price_per_token: Balance, sale_active: bool) -> Result<()> {
+            self.ensure_owner()?;
+            self.price_per_token = price_per_token;
+            self.sale_active = sale_active;
+            Ok(())
+        }
+
+        /// Limits what `mint` and `mint_batch` may create within one block;
+        /// `None` lifts the limit.
+        #[ink(message, selector = 0x519eceb7)]
+        pub fn set_mint_cap_per_block(&mut self, mint_cap_per_block: Option<Balance>) -> Result<()> {
+            self.ensure_owner()?;
+            self.mint_cap_per_block = mint_cap_per_block;
+            Ok(())
+        }
+
+        /// Sets the floor burns cannot push the total supply below.
+        ///
+        /// The floor can only be raised, so holders can rely on it.
+        #[ink(message, selector = 0xb8fa565a)]
+        pub fn set_min_supply(&mut self, min_supply: Balance) -> Result<()> {
+            self.ensure_owner()?;
+            if matches!(self.min_supply, Some(current) if min_supply < current) {
+                return Err(Error::MinSupplyLowered);
+            }
+            self.min_supply = Some(min_supply);
+            Ok(())
+        }
+
+        /// Limits the balance an account can build up through transfers;
+        /// `None` lifts the limit.
+        ///
+        /// The owner and the treasury may hold any amount.
+        #[ink(message, selector = 0xeab24a30)]
+        pub fn set_max_wallet_amount(&mut self, max_wallet_amount: Option<Balance>) -> Result<()> {
+            self.ensure_owner()?;
+            self.max_wallet_amount = max_wallet_amount;
+            Ok(())
+        }
+
+        /// Makes every account but the owner wait `cooldown` milliseconds
+        /// between two outgoing transfers; zero turns the cooldown off.
+        #[ink(message, selector = 0xf8a8bde6)]
+        pub fn set_transfer_cooldown(&mut self, cooldown: u64) -> Result<()> {
+            self.ensure_owner()?;
+            self.transfer_cooldown = cooldown;
+            Ok(())
+        }
+
+        /// Hands `mint`, `pause` and `unpause` over to `admins`, of whom
+        /// `threshold` must confirm each `AdminAction`.
+        ///
+        /// Can be called once; from then on the direct messages fail with
+        /// `MultisigRequired`.
+        #[ink(message, selector = 0xe961e906)]
+        pub fn set_multisig(&mut self, admins: Vec<AccountId>, threshold: u8) -> Result<()> {
+            self.ensure_owner()?;
+            self.ensure_no_multisig()?;
+            if admins.len() > MAX_BATCH_LEN {
+                return Err(Error::BatchTooLarge);
+            }
+            let mut admins = admins;
+            admins.sort();
+            admins.dedup();
+            if threshold == 0 || threshold as usize > admins.len() {
+                return Err(Error::InvalidThreshold);
+            }
+            self.admins = admins;
+            self.threshold = threshold;
+            Ok(())
+        }
+
+        /// Proposes `action` and counts as the caller's confirmation of it.
+        ///
+        /// Returns the hash other admins pass to `confirm_action`.
+        #[ink(message, selector = 0x0b64939b)]
+        pub fn propose_action(&mut self, action: AdminAction) -> Result<[u8; 32]> {
+            self.ensure_admin()?;
+            let action_hash = self.action_hash(action.clone());
+            if self.proposed_actions.get(action_hash).is_some() {
+                return Err(Error::ActionAlreadyProposed);
+            }
+            self.proposed_actions.insert(action_hash, &action);
+
+            Self::env().emit_event(
+                Proposed {
+                    action_hash,
+                    proposer: self.env().caller()
+                }
+            );
+
+            self.confirm_action(action_hash)?;
+            Ok(action_hash)
+        }
+
+        /// Confirms the pending action `action_hash`, executing it once
+        /// `threshold` distinct admins have confirmed.
+        #[ink(message, selector = 0xf41d20dc)]
+        pub fn confirm_action(&mut self, action_hash: [u8; 32]) -> Result<()> {
+            self.ensure_admin()?;
+            let action = self.proposed_actions.get(action_hash).ok_or(Error::UnknownAction)?;
+            let admin = self.env().caller();
+            let mut confirmations = self.confirmations.get(action_hash).unwrap_or_default();
+            if confirmations.contains(&admin) {
+                return Err(Error::AlreadyConfirmed);
+            }
+            confirmations.push(admin);
+
+            Self::env().emit_event(
+                Confirmed {
+                    action_hash,
+                    admin
+                }
+            );
+
+            if confirmations.len() < self.threshold as usize {
+                self.confirmations.insert(action_hash, &confirmations);
+                return Ok(());
+            }
+
+            self.proposed_actions.remove(action_hash);
+            self.confirmations.remove(action_hash);
+            self.execute_action(action)?;
+
+            Self::env().emit_event(Executed { action_hash });
+
+            Ok(())
+        }
+
+        /// Credits one page of balances carried over from a legacy
+        /// deployment, minting them into the total supply.
+        ///
+        /// Accounts that already hold a balance, including repeats within
+        /// the page, are skipped, so overlapping pages never credit twice.
+        #[ink(message, selector = 0xf0bf215b)]
+        pub fn migrate_balances(&mut self, entries: Vec<(AccountId, Balance)>) -> Result<()> {
+            self.ensure_owner()?;
+            if self.migration_complete {
+                return Err(Error::MigrationComplete);
+            }
+            if entries.len() > MAX_BATCH_LEN {
+                return Err(Error::BatchTooLarge);
+            }
+
+            let mut fresh: Vec<(AccountId, Balance)> = Vec::new();
+            for (account, value) in entries {
+                if value > 0
+                    && self.balances.get(account).is_none()
+                    && !fresh.iter().any(|(seen, _)| *seen == account)
+                {
+                    fresh.push((account, value));
+                }
+            }
+            let total = fresh
+                .iter()
+                .try_fold(self.total_supply, |acc, (_, value)| acc.checked_add(*value))
+                .ok_or(Error::Overflow)?;
+            if let Some(cap) = self.cap {
+                if total > cap {
+                    return Err(Error::CapExceeded);
+                }
+            }
+
+            for (account, value) in fresh {
+                self.inter_mint(account, value)?;
+            }
+
+            Ok(())
+        }
+
+        /// Turns `migrate_balances` off for good.
+        #[ink(message, selector = 0xc034b3a9)]
+        pub fn finish_migration(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            self.migration_complete = true;
+            Ok(())
+        }
+
+        /// Lifts the pre-launch whitelist restriction; cannot be undone.
+        #[ink(message, selector = 0x7f12c9c9)]
+        pub fn enable_trading(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            self.trading_enabled = true;
+            Ok(())
+        }
+
+        /// Holds back transfers of everyone but the owner until block
+        /// `launch_block`, so that the owner can seed liquidity first.
+        ///
+        /// Fails with `AlreadyLaunched` once the current launch block was
+        /// reached or anyone but the owner has transferred, so that it cannot
+        /// halt a live token.
+        #[ink(message, selector = 0x4cec9aa7)]
+        pub fn set_launch_block(&mut self, launch_block: Option<u32>) -> Result<()> {
+            self.ensure_owner()?;
+            if self.transfers_started {
+                return Err(Error::AlreadyLaunched);
+            }
+            if let Some(current) = self.launch_block {
+                if self.env().block_number() >= current {
+                    return Err(Error::AlreadyLaunched);
+                }
+            }
+            self.launch_block = launch_block;
+            Ok(())
+        }
+
+        /// Adds `account` to or removes it from the pre-launch whitelist.
+        #[ink(message, selector = 0xb5594f72)]
+        pub fn set_whitelisted(&mut self, account: AccountId, whitelisted: bool) -> Result<()> {
+            self.ensure_owner()?;
+            if whitelisted {
+                self.transfer_whitelist.insert(account, &true);
+            } else {
+                self.transfer_whitelist.remove(account);
+            }
+            Ok(())
+        }
+
+        #[ink(message, selector = 0x24d2da30)]
+        pub fn set_name(&mut self, name: Option<String>) -> Result<()> {
+            self.ensure_metadata_mutable()?;
+            self.name = name;
+            Ok(())
+        }
+
+        #[ink(message, selector = 0x5579dd63)]
+        pub fn set_symbol(&mut self, symbol: Option<String>) -> Result<()> {
+            self.ensure_metadata_mutable()?;
+            self.symbol = symbol;
+            Ok(())
+        }
+
+        /// Locks name, symbol and decimals for good.
+        #[ink(message, selector = 0xd8b6f2ad)]
+        pub fn freeze_metadata(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            self.metadata_frozen = true;
+            Ok(())
+        }
+
+        fn set_owner(&mut self, new_owner: AccountId) {
+            let previous = self.owner;
+            self.owner = new_owner;
+
+            Self::env().emit_event(
+                OwnershipTransferred {
+                    previous,
+                    new: new_owner
+                }
+            );
+        }
+
+        fn ensure_owner(&self) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::NotOwner);
+            }
+            Ok(())
+        }
+
+        fn ensure_metadata_mutable(&self) -> Result<()> {
+            self.ensure_owner()?;
+            if self.metadata_frozen {
+                return Err(Error::MetadataFrozen);
+            }
+            Ok(())
+        }
+
+        fn ensure_admin(&self) -> Result<()> {
+            if !self.admins.contains(&self.env().caller()) {
+                return Err(Error::NotAdmin);
+            }
+            Ok(())
+        }
+
+        fn ensure_no_multisig(&self) -> Result<()> {
+            if self.threshold > 0 {
+                return Err(Error::MultisigRequired);
+            }
+            Ok(())
+        }
+
+        fn execute_action(&mut self, action: AdminAction) -> Result<()> {
+            match action {
+                AdminAction::Mint { to, value } => self.rate_limited_mint(ink_prelude::vec![(to, value)])?,
+                AdminAction::Pause => {
+                    self.paused = true;
+                    Self::env().emit_event(
+                        Paused {
+                            account: self.env().caller()
+                        }
+                    );
+                }
+                AdminAction::Unpause => {
+                    self.paused = false;
+                    Self::env().emit_event(
+                        Unpaused {
+                            account: self.env().caller()
+                        }
+                    );
+                }
+            }
+            Ok(())
+        }
+
+        fn ensure_role(&self, role: RoleId) -> Result<()> {
+            if !self.has_role(role, self.env().caller()) {
+                return Err(Error::MissingRole);
+            }
+            Ok(())
+        }
+
+        /// Runs `body` with the reentrancy guard set, failing with
+        /// `ReentrantCall` if it already is.
+        ///
+        /// Only wraps messages that call out to other contracts, which could
+        /// otherwise call back in while our state is half updated. Our calls
+        /// use the default call flags, so the chain already rejects re-entry;
+        /// the guard keeps that protection if a call ever allows it.
+        ///
+        /// The flag lives in a `Mapping`, which is written to storage at once.
+        /// A root field is only written back when the message ends, so a
+        /// re-entrant call would still read it unset.
+        fn non_reentrant<T>(&mut self, body: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+            if self.entered.get(()).unwrap_or(false) {
+                return Err(Error::ReentrantCall);
+            }
+            self.entered.insert((), &true);
+            let result = body(self);
+            self.entered.remove(());
+            result
+        }
+
+        fn ensure_not_paused(&self) -> Result<()> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            Ok(())
+        }
+
+        fn ensure_not_frozen(&self, account: AccountId) -> Result<()> {
+            if self.is_frozen(account) {
+                return Err(Error::AccountFrozen);
+            }
+            Ok(())
+        }
+
+        /// Accounts whose transfers bypass the per-transaction limits.
+        fn is_limit_exempt(&self, account: AccountId) -> bool {
+            account == self.owner || Some(account) == self.treasury
+        }
+
+        fn ensure_within_max_tx(&self, from: AccountId, to: AccountId, value: Balance) -> Result<()> {
+            match self.max_tx_amount {
+                Some(max_tx_amount)
+                    if value > max_tx_amount
+                        && !self.is_limit_exempt(from)
+                        && !self.is_limit_exempt(to) =>
+                {
+                    Err(Error::MaxTxExceeded)
+                }
+                _ => Ok(()),
+            }
+        }
+
+        /// Tokens of `who` that the contract holds back: unreleased vesting and
+        /// the stake.
+        fn locked_balance(&self, who: AccountId) -> Balance {
+            let vesting = self.vestings
+                .get(who)
+                .map(|vesting| vesting.total - vesting.released)
+                .unwrap_or(0);
+            vesting.saturating_add(self.staked_of(who))
+        }
+
+        /// Mints to every `(to, value)` pair, counting the total against
+        /// `mint_cap_per_block`.
+        ///
+        /// The summed amount is checked against both caps before anything is
+        /// minted, and the per-block counter is only stored afterwards.
+        fn rate_limited_mint(&mut self, recipients: Vec<(AccountId, Balance)>) -> Result<()> {
+            let total = recipients
+                .iter()
+                .try_fold(0 as Balance, |acc, (_, value)| acc.checked_add(*value))
+                .ok_or(Error::Overflow)?;
+            let new_total_supply = self.total_supply().checked_add(total).ok_or(Error::Overflow)?;
+            if let Some(cap) = self.cap {
+                if new_total_supply > cap {
+                    return Err(Error::CapExceeded);
+                }
+            }
+            let minted_this_block = self.minted_this_block_after(total)?;
+
+            for (to, value) in recipients {
+                self.inter_mint(to, value)?;
+            }
+            self.minted_this_block = minted_this_block;
+
+            Ok(())
+        }
+
+        /// Pays out the pending emission before its rate or recipient changes.
+        ///
+        /// If it cannot be minted, for instance while the token is paused or
+        /// at its cap, it is forfeited and the emission restarts from the
+        /// current block.
+        fn settle_emission(&mut self) {
+            if self.mint_emission().is_err() {
+                self.last_emission_block = self.env().block_number();
+            }
+        }
+
+        /// Returns the `(block, minted)` counter after minting `value` more in
+        /// the current block, which starts at zero whenever the block changes.
+        ///
+        /// Fails with `MintRateExceeded` past `mint_cap_per_block`; nothing is
+        /// written, so the caller stores the counter once the mint succeeded.
+        fn minted_this_block_after(&self, value: Balance) -> Result<(u32, Balance)> {
+            let block = self.env().block_number();
+            let (last_block, minted) = self.minted_this_block;
+            let minted = if last_block == block { minted } else { 0 };
+            let new_minted = minted.checked_add(value).ok_or(Error::Overflow)?;
+            if let Some(mint_cap_per_block) = self.mint_cap_per_block {
+                if new_minted > mint_cap_per_block {
+                    return Err(Error::MintRateExceeded);
+                }
+            }
+            Ok((block, new_minted))
+        }
+
+        /// Checks that receiving `value` more tokens keeps `to` within
+        /// `max_wallet_amount`.
+        fn ensure_within_max_wallet(&self, to: AccountId, value: Balance) -> Result<()> {
+            match self.max_wallet_amount {
+                Some(max_wallet_amount) if !self.is_limit_exempt(to) => {
+                    let new_balance = self.balance_of(to).checked_add(value).ok_or(Error::Overflow)?;
+                    if new_balance > max_wallet_amount {
+                        return Err(Error::MaxWalletExceeded);
+                    }
+                    Ok(())
+                }
+                _ => Ok(()),
+            }
+        }
+
+        /// Checks the cooldown of `from` and restarts it.
+        fn enforce_cooldown(&mut self, from: AccountId) -> Result<()> {
+            if self.transfer_cooldown == 0 || from == self.owner {
+                return Ok(());
+            }
+            let now = self.block_timestamp();
+            if let Some(last_transfer) = self.last_transfer.get(from) {
+                if now.saturating_sub(last_transfer) < self.transfer_cooldown {
+                    return Err(Error::CooldownActive);
+                }
+            }
+            self.last_transfer.insert(from, &now);
+            Ok(())
+        }
+
+        fn ensure_not_zero_address(&self, account: AccountId) -> Result<()> {
+            if self.check_zero_address && account == AccountId::from([0u8; 32]) {
+                return Err(Error::ZeroAddress);
+            }
+            Ok(())
+        }
+
+        /// Called before tokens are transferred, minted or burned; `from` is
+        /// `None` for mints and `to` is `None` for burns.
+        ///
+        /// Rejects the operation while the contract is paused or if a frozen
+        /// account takes part.
+        fn _before_token_transfer(
+            &mut self,
+            from: Option<AccountId>,
+            to: Option<AccountId>,
+            _value: Balance,
+        ) -> Result<()> {
+            self.ensure_not_paused()?;
+            if let Some(from) = from {
+                self.ensure_not_frozen(from)?;
+            }
+            if let Some(to) = to {
+                self.ensure_not_frozen(to)?;
+            }
+            Ok(())
+        }
+
+        /// Hashes the contract's name and account id.
+        ///
+        /// ink! exposes no chain id, so the account id, which differs between
+        /// deployments, stands in for it.
+        fn build_domain_separator(&self) -> [u8; 32] {
+            self.env().hash_encoded::<Blake2x256, _>(&(
+                b"Erc20",
+                &self.name,
+                self.env().account_id(),
+            ))
+        }
+
+        /// The message an owner signs to grant a `permit`.
+        fn permit_hash(
+            &self,
+            owner: AccountId,
+            spender: AccountId,
+            value: Balance,
+            nonce: u64,
+            deadline: u64,
+        ) -> [u8; 32] {
+            self.env().hash_encoded::<Blake2x256, _>(&(
+                self.build_domain_separator(),
+                b"Erc20::permit",
+                owner,
+                spender,
+                value,
+                nonce,
+                deadline,
+            ))
+        }
+
+        /// Fails early with `InsufficientApproval`, so that a missing approval
+        /// is reported before a missing balance.
+        fn ensure_allowance(&self, owner: AccountId, spender: AccountId, value: Balance) -> Result<()> {
+            if value > self.allowance(owner, spender) {
+                return Err(Error::InsufficientApproval);
+            }
+            Ok(())
+        }
+
+        /// Fails with `PeriodicAllowance` if `spender` has a periodic allowance
+        /// over the tokens of `owner`, whose plain allowance only mirrors the limit.
+        fn ensure_not_periodic(&self, owner: AccountId, spender: AccountId) -> Result<()> {
+            if self.periodic_allowances.get((owner, spender)).is_some() {
+                return Err(Error::PeriodicAllowance);
+            }
+            Ok(())
+        }
+
+        /// Returns the periodic allowance of `spender` after pulling `value`,
+        /// or `None` if the pair has a plain allowance.
+        ///
+        /// Nothing is written, so the caller stores the result only once the
+        /// transfer went through.
+        fn spend_periodic_allowance(
+            &self,
+            owner: AccountId,
+            spender: AccountId,
+            value: Balance,
+        ) -> Result<Option<(Balance, u64, Balance, u64)>> {
+            let (limit, period, mut used, mut window_start) = match self.periodic_allowances.get((owner, spender)) {
+                Some(periodic) => periodic,
+                None => return Ok(None),
+            };
+            let now = self.block_timestamp();
+            if now >= window_start.saturating_add(period.saturating_mul(1_000)) {
+                used = 0;
+                window_start = now;
+            }
+            let new_used = used
+                .checked_add(value)
+                .filter(|new_used| *new_used <= limit)
+                .ok_or(Error::PeriodLimitExceeded)?;
+            Ok(Some((limit, period, new_used, window_start)))
+        }
+
+        /// Takes `value` out of the allowance of `spender` over the tokens of
+        /// `owner` and emits the remaining allowance.
+        ///
+        /// An allowance of `Balance::MAX` is treated as infinite: it is never
+        /// decreased and no `Approval` event is emitted for it.
+        fn spend_allowance(&mut self, owner: AccountId, spender: AccountId, value: Balance) -> Result<()> {
+            let allowance = self.allowance(owner, spender);
+            if allowance == Balance::MAX {
+                return Ok(());
+            }
+            let new_allowance = allowance.checked_sub(value).ok_or(Error::InsufficientApproval)?;
+            self.set_allowance(owner, spender, new_allowance);
+
+            Self::env().emit_event(
+                Approval {
+                    owner,
+                    spender,
+                    value: new_allowance
+                }
+            );
+
+            Ok(())
+        }
+
+        fn inter_approve(&mut self, owner: AccountId, spender: AccountId, value: Balance) -> Result<()> {
+            self.ensure_not_paused()?;
+            self.ensure_not_frozen(owner)?;
+            self.ensure_not_zero_address(spender)?;
+            self.set_allowance(owner, spender, value);
+            self.allowance_expiry.remove((owner, spender));
+            self.periodic_allowances.remove((owner, spender));
+
+            Self::env().emit_event(
+                Approval {
+                    owner,
+                    spender,
+                    value
+                }
+            );
+
+            Ok(())
+        }
+
+        /// Transfers `value` tokens, charging `fee_bps` as read by the calling
+        /// message through `current_fee_bps`.
+        fn inter_transfer(&mut self, from: AccountId, to: AccountId, value: Balance, fee_bps: u16) -> Result<()> {
+            let result = match self.large_transfer_threshold {
+                Some(threshold) if value > threshold => Err(Error::TimelockRequired),
+                _ => self.inter_transfer_unlocked(from, to, value, fee_bps),
+            };
+            if let Err(error) = &result {
+                if self.emit_failures {
+                    Self::env().emit_event(
+                        TransferFailed {
+                            from,
+                            to,
+                            value,
+                            reason: scale::Encode::encode(error)[0]
+                        }
+                    );
+                }
+            }
+            result
+        }
+
+        fn inter_transfer_unlocked(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            fee_bps: u16,
+        ) -> Result<()> {
+            self._before_token_transfer(Some(from), Some(to), value)?;
+            if !self.trading_enabled && !self.is_whitelisted(from) && !self.is_whitelisted(to) {
+                return Err(Error::TradingNotEnabled);
+            }
+            if let Some(launch_block) = self.launch_block {
+                if from != self.owner && self.env().block_number() < launch_block {
+                    return Err(Error::NotLaunchedYet);
+                }
+            }
+            self.ensure_not_zero_address(to)?;
+            self.ensure_within_max_tx(from, to, value)?;
+
+            // Vesting and staked tokens are held by the contract, outside of
+            // `balance_of`, so the whole balance is spendable.
+            let available = self.balance_of(from);
+            if value > available {
+                if value <= available.saturating_add(self.locked_balance(from)) {
+                    return Err(Error::InsufficientUnlockedBalance);
+                }
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.enforce_cooldown(from)?;
+
+            let fee = self.apply_bps(value, fee_bps);
+            // Rounding both shares up may take one unit more than the value.
+            let burned = self.apply_bps(value, self.burn_bps).min(value - fee);
+
+            if from != to {
+                self.ensure_within_max_wallet(to, value - fee - burned)?;
+            }
+            if burned > 0 {
+                self.supply_after_decrease(burned)?;
+            }
+
+            self.move_balance(from, to, value - fee - burned)?;
+            if let Some(treasury) = self.treasury.filter(|_| fee > 0) {
+                self.move_balance(from, treasury, fee)?;
+            }
+            if burned > 0 {
+                self.inter_burn(from, burned)?;
+            }
+            if from != self.owner && !self.transfers_started {
+                self.transfers_started = true;
+            }
+
+            Ok(())
+        }
+
+        /// Moves `value` tokens from `from` to `to` and emits the `Transfer` event.
+        fn move_balance(&mut self, from: AccountId, to: AccountId, value: Balance) -> Result<()> {
+            // A transfer to oneself moves nothing, so storage is left alone;
+            // the tokens still have to exist.
+            if from == to {
+                if value > self.balances.get(from).unwrap_or(0) {
+                    return Err(Error::InsufficientBalance);
+                }
+                Self::env().emit_event(
+                    Transfer {
+                        from: Some(from),
+                        to: Some(to),
+                        value
+                    }
+                );
+                self.emit_indexed_transfer(Some(from), Some(to), value);
+                return Ok(());
+            }
+
+            let from_balance = self.balances.get(from).unwrap_or(0);
+            let to_balance = self.balances.get(to).unwrap_or(0);
+
+            let new_from_balance = from_balance.checked_sub(value).ok_or(Error::InsufficientBalance)?;
+            let new_to_balance = to_balance.checked_add(value).ok_or(Error::Overflow)?;
+
+            self.update_account_snapshot(from);
+            self.update_account_snapshot(to);
+            self.set_balance(from, new_from_balance);
+            self.set_balance(to, new_to_balance);
+            self.move_voting_power(self.delegate_of(from), self.delegate_of(to), value);
+
+            Self::env().emit_event(
+                Transfer {
+                    from: Some(from),
+                    to: Some(to),
+                    value
+                }
+            );
+            self.emit_indexed_transfer(Some(from), Some(to), value);
+
+            Ok(())
+        }
+
+        fn emit_indexed_transfer(&self, from: Option<AccountId>, to: Option<AccountId>, value: Balance) {
+            if self.index_values {
+                Self::env().emit_event(TransferIndexed { from, to, value });
+            }
+        }
+
+        fn inter_mint(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            self._before_token_transfer(None, Some(to), value)?;
+            let to_balance = self.balances.get(to).unwrap_or(0);
+
+            let new_to_balance = to_balance.checked_add(value).ok_or(Error::Overflow)?;
+            let new_total_supply = self.total_supply.checked_add(value).ok_or(Error::Overflow)?;
+            let new_total_minted = self.total_minted.checked_add(value).ok_or(Error::Overflow)?;
+
+            if let Some(cap) = self.cap {
+                if new_total_supply > cap {
+                    return Err(Error::CapExceeded);
+                }
+            }
+
+            self.update_account_snapshot(to);
+            self.update_total_supply_snapshot();
+            self.set_balance(to, new_to_balance);
+            self.total_supply = new_total_supply;
+            self.total_minted = new_total_minted;
+            self.move_voting_power(None, self.delegate_of(to), value);
+
+            if !self.skip_zero_address_transfers {
+                Self::env().emit_event(
+                    Transfer {
+                        from: None,
+                        to: Some(to),
+                        value
+                    }
+                );
+                self.emit_indexed_transfer(None, Some(to), value);
+            }
+            if self.mint_burn_events {
+                Self::env().emit_event(Mint { to, value });
+            }
+
+            Ok(())
+        }
+
+        fn inter_burn(&mut self, from: AccountId, value: Balance) -> Result<()> {
+            self._before_token_transfer(Some(from), None, value)?;
+            let from_balance = self.balances.get(from).unwrap_or(0);
+
+            if value > from_balance {
+                return Err(Error::InsufficientBalance);
+            }
+
+            let new_from_balance = from_balance.checked_sub(value).ok_or(Error::InsufficientBalance)?;
+            let new_total_burned = self.total_burned.checked_add(value).ok_or(Error::Overflow)?;
+
+            // All writes happen only after every check passed, so the supply
+            // always matches the sum of balances; `decrease_supply` checks
+            // before it writes.
+            self.decrease_supply(value)?;
+            self.update_account_snapshot(from);
+            self.set_balance(from, new_from_balance);
+            self.total_burned = new_total_burned;
+            self.move_voting_power(self.delegate_of(from), None, value);
+
+            if !self.skip_zero_address_transfers {
+                Self::env().emit_event(
+                    Transfer {
+                        from: Some(from),
+                        to: None,
+                        value
+                    }
+                );
+                self.emit_indexed_transfer(Some(from), None, value);
+            }
+            if self.mint_burn_events {
+                Self::env().emit_event(Burn { from, value });
+            }
+
+            Ok(())
+        }
+
+        /// Takes `value` off the total supply, failing with `Overflow` instead
+        /// of wrapping if the supply is smaller and with `MinSupplyBreached`
+        /// if it would drop below `min_supply`.
+        ///
+        /// Every burn path goes through here, so that no combination of fees
+        /// and burns can push the supply below zero.
+        fn decrease_supply(&mut self, value: Balance) -> Result<()> {
+            let new_total_supply = self.supply_after_decrease(value)?;
+            self.update_total_supply_snapshot();
+            self.total_supply = new_total_supply;
+            Ok(())
+        }
+
+        /// Returns the total supply after taking `value` off, with the checks
+        /// of `decrease_supply` but without writing anything.
+        fn supply_after_decrease(&self, value: Balance) -> Result<Balance> {
+            let new_total_supply = self.total_supply.checked_sub(value).ok_or(Error::Overflow)?;
+            if let Some(min_supply) = self.min_supply {
+                if new_total_supply < min_supply {
+                    return Err(Error::MinSupplyBreached);
+                }
+            }
+            Ok(new_total_supply)
+        }
+
+        /// Rewards earned per staked token since the first stake, scaled by
+        /// `REWARD_PRECISION`.
+        fn current_reward_per_token(&self) -> u128 {
+            let elapsed = self.env().block_number().saturating_sub(self.last_reward_block) as u128;
+            self.reward_per_token.saturating_add(self.reward_rate.saturating_mul(elapsed))
+        }
+
+        /// Brings `reward_per_token` up to the current block at the current
+        /// rate; called before the rate changes.
+        fn update_reward_per_token(&mut self) {
+            self.reward_per_token = self.current_reward_per_token();
+            self.last_reward_block = self.env().block_number();
+        }
+
+        /// Rewards earned by the stake of `account` since its last update.
+        fn new_rewards(&self, account: AccountId) -> Balance {
+            let (amount, paid_per_token) = self.staked.get(account).unwrap_or((0, 0));
+            amount.saturating_mul(self.current_reward_per_token() - paid_per_token) / REWARD_PRECISION
+        }
+
+        /// Moves the rewards earned so far into `accrued_rewards` and returns
+        /// the staked amount, whose entry the caller must reset to the
+        /// updated `reward_per_token`.
+        fn settle_rewards(&mut self, account: AccountId) -> Result<Balance> {
+            self.update_reward_per_token();
+            let (amount, paid_per_token) = self.staked.get(account).unwrap_or((0, 0));
+            let reward = amount
+                .checked_mul(self.reward_per_token - paid_per_token)
+                .ok_or(Error::Overflow)?
+                / REWARD_PRECISION;
+            if reward > 0 {
+                let accrued = self.accrued_rewards.get(account).unwrap_or(0);
+                let new_accrued = accrued.checked_add(reward).ok_or(Error::Overflow)?;
+                self.accrued_rewards.insert(account, &new_accrued);
+            }
+            Ok(amount)
+        }
+
+        /// Dividends earned by `balance` tokens of `account` since its last
+        /// claim point.
+        fn new_dividends(&self, account: AccountId, balance: Balance) -> Balance {
+            let last_point = self.last_claim_point.get(account).unwrap_or(0);
+            balance.saturating_mul(self.dividend_per_share - last_point) / DIVIDEND_PRECISION
+        }
+
+        /// Credits the dividends `account` earned with its old `balance` and
+        /// moves its claim point up, so that dividends follow balances.
+        fn settle_dividends(&mut self, account: AccountId, balance: Balance) {
+            let last_point = self.last_claim_point.get(account).unwrap_or(0);
+            if last_point == self.dividend_per_share {
+                return;
+            }
+            let earned = self.new_dividends(account, balance);
+            if earned > 0 {
+                let unclaimed = self.unclaimed_dividends.get(account).unwrap_or(0);
+                self.unclaimed_dividends.insert(account, &unclaimed.saturating_add(earned));
+            }
+            self.last_claim_point.insert(account, &self.dividend_per_share);
+        }
+
+        /// Returns whether the deadline of the allowance of `spender` over the
+        /// tokens of `owner` has passed.
+        fn allowance_expired(&self, owner: AccountId, spender: AccountId) -> bool {
+            match self.allowance_expiry.get((owner, spender)) {
+                Some(deadline) => self.block_timestamp() > deadline,
+                None => false,
+            }
+        }
+
+        /// Stores an allowance and keeps track of the pairs with one.
+        fn set_allowance(&mut self, owner: AccountId, spender: AccountId, value: Balance) {
+            let old_value = self.allowances.get((owner, spender)).unwrap_or(0);
+            if old_value == 0 && value > 0 {
+                self.allowance_pairs.insert(self.allowance_pair_count, &(owner, spender));
+                self.allowance_pair_index.insert((owner, spender), &self.allowance_pair_count);
+                self.allowance_pair_count += 1;
+                let spender_count = self.spender_count.get(owner).unwrap_or(0);
+                self.spenders.insert((owner, spender_count), &spender);
+                self.spender_index.insert((owner, spender), &spender_count);
+                self.spender_count.insert(owner, &(spender_count + 1));
+            } else if old_value > 0 && value == 0 {
+                self.remove_allowance_pair(owner, spender);
+                self.remove_spender(owner, spender);
+            }
+            self.allowances.insert((owner, spender), &value);
+            self.record_approval(owner, spender, value);
+        }
+
+        /// Removes `(owner, spender)` from the pairs with an allowance by
+        /// moving the last pair into its slot.
+        fn remove_allowance_pair(&mut self, owner: AccountId, spender: AccountId) {
+            let index = match self.allowance_pair_index.get((owner, spender)) {
+                Some(index) => index,
+                None => return,
+            };
+            let last_index = self.allowance_pair_count - 1;
+            if index != last_index {
+                if let Some(last) = self.allowance_pairs.get(last_index) {
+                    self.allowance_pairs.insert(index, &last);
+                    self.allowance_pair_index.insert(last, &index);
+                }
+            }
+            self.allowance_pairs.remove(last_index);
+            self.allowance_pair_index.remove((owner, spender));
+            self.allowance_pair_count = last_index;
+        }
+
+        /// Removes `spender` from the spenders of `owner` by moving the last
+        /// spender into its slot.
+        fn remove_spender(&mut self, owner: AccountId, spender: AccountId) {
+            let index = match self.spender_index.get((owner, spender)) {
+                Some(index) => index,
+                None => return,
+            };
+            let last_index = self.spender_count.get(owner).unwrap_or(0) - 1;
+            if index != last_index {
+                if let Some(last) = self.spenders.get((owner, last_index)) {
+                    self.spenders.insert((owner, index), &last);
+                    self.spender_index.insert((owner, last), &index);
+                }
+            }
+            self.spenders.remove((owner, last_index));
+            self.spender_index.remove((owner, spender));
+            if last_index == 0 {
+                self.spender_count.remove(owner);
+            } else {
+                self.spender_count.insert(owner, &last_index);
+            }
+        }
+
+        /// Appends an allowance change to the history of `owner`, dropping
+        /// the oldest entry once `APPROVAL_HISTORY_LEN` are stored.
+        fn record_approval(&mut self, owner: AccountId, spender: AccountId, value: Balance) {
+            let mut history = self.approval_history.get(owner).unwrap_or_default();
+            if history.len() >= APPROVAL_HISTORY_LEN {
+                history.remove(0);
+            }
+            history.push((spender, value, self.block_timestamp()));
+            self.approval_history.insert(owner, &history);
+        }
+
+        /// Stores the balance of `account` and keeps track of who holds tokens.
+        fn set_balance(&mut self, account: AccountId, new_balance: Balance) {
+            let old_balance = self.balance_of(account);
+            self.settle_dividends(account, old_balance);
+            self.write_weight_checkpoint(account, new_balance);
+            if old_balance == 0 && new_balance > 0 {
+                if self.auto_self_delegate && self.delegate_of(account).is_none() {
+                    // Delegated while the balance is still zero, so the
+                    // caller moves the new tokens' votes as usual.
+                    self.inter_delegate(account, account);
+                }
+                self.holders.insert(self.holder_count, &account);
+                self.holder_index.insert(account, &self.holder_count);
+                self.holder_count += 1;
+            } else if old_balance > 0 && new_balance == 0 {
+                self.remove_holder(account);
+            }
+            self.balances.insert(account, &new_balance);
+        }
+
+        /// Removes `account` from the holder list by moving the last holder
+        /// into its slot.
+        fn remove_holder(&mut self, account: AccountId) {
+            let index = match self.holder_index.get(account) {
+                Some(index) => index,
+                None => return,
+            };
+            let last_index = self.holder_count - 1;
+            if index != last_index {
+                if let Some(last) = self.holders.get(last_index) {
+                    self.holders.insert(index, &last);
+                    self.holder_index.insert(last, &index);
+                }
+            }
+            self.holders.remove(last_index);
+            self.holder_index.remove(account);
+            self.holder_count = last_index;
+        }
+
+        /// Every holder, for the views that are capped at `MAX_EXPORT_ENTRIES`.
+        fn all_holders(&self) -> Vec<AccountId> {
+            (0..self.holder_count).filter_map(|index| self.holders.get(index)).collect()
+        }
+
+        /// Returns the number of leading entries among the first `len` for
+        /// which `pred` holds, given that it holds for a prefix of them.
+        ///
+        /// Binary search over checkpoints stored one per `Mapping` entry, so a
+        /// lookup reads O(log len) entries.
+        fn partition_point(len: u32, pred: impl Fn(u32) -> bool) -> u32 {
+            let (mut low, mut high) = (0, len);
+            while low < high {
+                let mid = low + (high - low) / 2;
+                if pred(mid) {
+                    low = mid + 1;
+                } else {
+                    high = mid;
+                }
+            }
+            low
+        }
+
+        /// Sums up balance × time of `account` from its first checkpoint until
+        /// `timestamp`.
+        ///
+        /// Each checkpoint holds when a balance changed, the weight
+        /// accumulated until then and the balance from then on.
+        fn weight_at(&self, account: AccountId, timestamp: u64) -> u128 {
+            let len = self.balance_weight_count.get(account).unwrap_or(0);
+            let index = Self::partition_point(len, |index| {
+                self.balance_weights
+                    .get((account, index))
+                    .is_some_and(|(checkpoint_time, _, _)| checkpoint_time <= timestamp)
+            });
+            index
+                .checked_sub(1)
+                .and_then(|index| self.balance_weights.get((account, index)))
+                .map(|(checkpoint_time, weight, balance)| {
+                    let held = (timestamp - checkpoint_time) as u128;
+                    weight.saturating_add(balance.saturating_mul(held))
+                })
+                .unwrap_or(0)
+        }
+
+        /// Records that `account` holds `new_balance` from now on, overwriting
+        /// the latest checkpoint if it was written at the same timestamp.
+        fn write_weight_checkpoint(&mut self, account: AccountId, new_balance: Balance) {
+            let now = self.block_timestamp();
+            let len = self.balance_weight_count.get(account).unwrap_or(0);
+            let weight = self.weight_at(account, now);
+            let last = len.checked_sub(1).and_then(|index| self.balance_weights.get((account, index)));
+            match last {
+                Some((last_time, last_weight, _)) if last_time == now => {
+                    self.balance_weights.insert((account, len - 1), &(now, last_weight, new_balance));
+                }
+                _ => {
+                    self.balance_weights.insert((account, len), &(now, weight, new_balance));
+                    self.balance_weight_count.insert(account, &(len + 1));
+                }
+            }
+        }
+
+        /// The transfer fee in basis points, read from the oracle if one is
+        /// set. Messages call this once and pass the fee on to every transfer
+        /// they make, so a batch costs one oracle call and has one fee.
+        ///
+        /// Without a treasury there is nowhere to send a fee, so it is zero.
+        fn current_fee_bps(&self) -> u16 {
+            if self.treasury.is_none() {
+                return 0;
+            }
+            match self.oracle {
+                Some(oracle) => self
+                    .call_fee_oracle(oracle)
+                    .filter(|fee_bps| *fee_bps as u32 + self.burn_bps as u32 <= 10_000)
+                    .unwrap_or(self.fee_bps),
+                None => self.fee_bps,
+            }
+        }
+
+        /// Returns `bps` basis points of `amount`, rounded by `rounding_mode`.
+        ///
+        /// Whole multiples of 10 000 are split off first, so the product
+        /// cannot overflow for `bps` up to 10 000.
+        fn apply_bps(&self, amount: Balance, bps: u16) -> Balance {
+            let bps = bps as Balance;
+            let remainder = (amount % 10_000) * bps;
+            let share = (amount / 10_000).saturating_mul(bps).saturating_add(remainder / 10_000);
+            let round_up = match self.rounding_mode {
+                RoundingMode::Down => false,
+                RoundingMode::Up => !remainder.is_multiple_of(10_000),
+                RoundingMode::Nearest => remainder % 10_000 >= 5_000,
+            };
+            if round_up {
+                share.saturating_add(1)
+            } else {
+                share
+            }
+        }
+
+        /// Returns 10^`exponent`, or `None` if it does not fit into a `Balance`.
+        fn pow10(exponent: u32) -> Option<Balance> {
+            (10 as Balance).checked_pow(exponent)
+        }
+
+        /// Looks up the value recorded for snapshot `id` among the `len`
+        /// snapshots that `snapshot_at` returns by index, oldest first.
+        ///
+        /// Returns `None` if the value did not change since `id` was taken, in
+        /// which case the current value applies.
+        fn value_at(
+            len: u32,
+            snapshot_at: impl Fn(u32) -> Option<(u64, Balance)>,
+            id: u64,
+            current_id: u64,
+        ) -> Option<Balance> {
+            if id == 0 || id > current_id {
+                return None;
+            }
+            let index = Self::partition_point(len, |index| {
+                snapshot_at(index).is_some_and(|(snapshot_id, _)| snapshot_id < id)
+            });
+            snapshot_at(index).map(|(_, value)| value)
+        }
+
+        /// Stores the balance of `account` before its first change since the
+        /// latest snapshot.
+        fn update_account_snapshot(&mut self, account: AccountId) {
+            let current_id = self.current_snapshot_id;
+            if current_id == 0 {
+                return;
+            }
+            let len = self.account_snapshot_count.get(account).unwrap_or(0);
+            let last = len.checked_sub(1).and_then(|index| self.account_snapshots.get((account, index)));
+            if last.is_none_or(|(id, _)| id < current_id) {
+                self.account_snapshots.insert((account, len), &(current_id, self.balance_of(account)));
+                self.account_snapshot_count.insert(account, &(len + 1));
+            }
+        }
+
+        /// Stores the total supply before its first change since the latest snapshot.
+        fn update_total_supply_snapshot(&mut self) {
+            let current_id = self.current_snapshot_id;
+            if current_id == 0 {
+                return;
+            }
+            let len = self.total_supply_snapshot_count;
+            let last = len.checked_sub(1).and_then(|index| self.total_supply_snapshots.get(index));
+            if last.is_none_or(|(id, _)| id < current_id) {
+                let total_supply = self.total_supply();
+                self.total_supply_snapshots.insert(len, &(current_id, total_supply));
+                self.total_supply_snapshot_count = len + 1;
+            }
+        }
+
+        fn delegate_of(&self, account: AccountId) -> Option<AccountId> {
+            self.delegates.get(account)
+        }
+
+        fn inter_delegate(&mut self, delegator: AccountId, delegatee: AccountId) {
+            let from_delegate = self.delegate_of(delegator);
+            self.delegates.insert(delegator, &delegatee);
+
+            Self::env().emit_event(
+                DelegateChanged {
+                    delegator,
+                    from_delegate,
+                    to_delegate: delegatee
+                }
+            );
+
+            let balance = self.balance_of(delegator);
+            self.move_voting_power(from_delegate, Some(delegatee), balance);
+        }
+
+        /// Moves `amount` votes from the delegate `src` to the delegate `dst`.
+        ///
+        /// Votes never exceed the total supply, so the arithmetic cannot overflow.
+        fn move_voting_power(&mut self, src: Option<AccountId>, dst: Option<AccountId>, amount: Balance) {
+            if src == dst || amount == 0 {
+                return;
+            }
+            if let Some(src) = src {
+                let previous_votes = self.get_votes(src);
+                self.write_vote_checkpoint(src, previous_votes, previous_votes.saturating_sub(amount));
+            }
+            if let Some(dst) = dst {
+                let previous_votes = self.get_votes(dst);
+                self.write_vote_checkpoint(dst, previous_votes, previous_votes.saturating_add(amount));
+            }
+        }
+
+        /// Records the votes of `delegate` at the current block, overwriting
+        /// the latest checkpoint if it was written in the same block.
+        fn write_vote_checkpoint(&mut self, delegate: AccountId, previous_votes: Balance, new_votes: Balance) {
+            let block = self.env().block_number();
+            let len = self.vote_checkpoint_count.get(delegate).unwrap_or(0);
+            let last = len.checked_sub(1).and_then(|index| self.vote_checkpoints.get((delegate, index)));
+            match last {
+                Some((last_block, _)) if last_block == block => {
+                    self.vote_checkpoints.insert((delegate, len - 1), &(block, new_votes));
+                }
+                _ => {
+                    self.vote_checkpoints.insert((delegate, len), &(block, new_votes));
+                    self.vote_checkpoint_count.insert(delegate, &(len + 1));
+                }
+            }
+
+            Self::env().emit_event(
+                DelegateVotesChanged {
+                    delegate,
+                    previous_votes,
+                    new_votes
+                }
+            );
+        }
+
+        #[cfg(not(test))]
+        fn notify_receiver(
+            &mut self,
+            operator: AccountId,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            data: Vec<u8>,
+        ) -> Result<()> {
+            if !self.env().is_contract(&to) {
+                return Ok(());
+            }
+            let response = build_call::<<Self as ::ink_lang::reflect::ContractEnv>::Env>()
+                .call_type(Call::new().callee(to).gas_limit(0))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ON_TOKEN_RECEIVED_SELECTOR))
+                        .push_arg(operator)
+                        .push_arg(from)
+                        .push_arg(value)
+                        .push_arg(data),
+                )
+                .returns::<[u8; 4]>()
+                .fire()
+                .map_err(|_| Error::TransferCallbackFailed)?;
+            if response != ON_TOKEN_RECEIVED_SELECTOR {
+                return Err(Error::TransferCallbackFailed);
+            }
+            Ok(())
+        }
+
+        /// The off-chain environment cannot call other contracts, so tests
+        /// register receiver responses through `mock`.
+        #[cfg(test)]
+        fn notify_receiver(
+            &mut self,
+            _operator: AccountId,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> Result<()> {
+            if mock::is_reentrant_receiver(to) {
+                // The receiver sends the tokens straight back the same way.
+                ink_env::test::set_caller::<ink_env::DefaultEnvironment>(to);
+                let reentry = self.transfer_and_call(from, value, Vec::new());
+                mock::record_reentry(reentry);
+            }
+            match mock::receiver_response(to) {
+                None => Ok(()),
+                Some(Some(response)) if response == ON_TOKEN_RECEIVED_SELECTOR => Ok(()),
+                Some(_) => Err(Error::TransferCallbackFailed),
+            }
+        }
+
+        #[cfg(not(test))]
+        fn call_flash_borrower(
+            &mut self,
+            initiator: AccountId,
+            receiver: AccountId,
+            amount: Balance,
+            fee: Balance,
+            data: Vec<u8>,
+        ) -> Result<()> {
+            let response = build_call::<<Self as ::ink_lang::reflect::ContractEnv>::Env>()
+                .call_type(Call::new().callee(receiver).gas_limit(0))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ON_FLASH_LOAN_SELECTOR))
+                        .push_arg(initiator)
+                        .push_arg(amount)
+                        .push_arg(fee)
+                        .push_arg(data),
+                )
+                .returns::<[u8; 4]>()
+                .fire()
+                .map_err(|_| Error::TransferCallbackFailed)?;
+            if response != ON_FLASH_LOAN_SELECTOR {
+                return Err(Error::TransferCallbackFailed);
+            }
+            Ok(())
+        }
+
+        #[cfg(not(test))]
+        fn call_token_transfer(&mut self, token: AccountId, to: AccountId, amount: Balance) -> Result<()> {
+            // Only the `Ok`/`Err` tag matters, so any error payload of the
+            // foreign token decodes as `()`.
+            build_call::<<Self as ::ink_lang::reflect::ContractEnv>::Env>()
+                .call_type(Call::new().callee(token).gas_limit(0))
+                .exec_input(
+                    ExecutionInput::new(Selector::new([0xdb, 0x20, 0xf9, 0xf5]))
+                        .push_arg(to)
+                        .push_arg(amount)
+                        .push_arg(Vec::<u8>::new()),
+                )
+                .returns::<core::result::Result<(), ()>>()
+                .fire()
+                .map_err(|_| Error::RecoveryFailed)?
+                .map_err(|_| Error::RecoveryFailed)
+        }
+
+        #[cfg(not(test))]
+        fn call_token_balance_of(&self, token: AccountId, owner: AccountId) -> Result<Balance> {
+            build_call::<<Self as ::ink_lang::reflect::ContractEnv>::Env>()
+                .call_type(Call::new().callee(token).gas_limit(0))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(selectors::PSP22_BALANCE_OF))
+                        .push_arg(owner),
+                )
+                .returns::<Balance>()
+                .fire()
+                .map_err(|_| Error::RecoveryFailed)
+        }
+
+        /// Sends `amount` native currency to `to`, returning whether it arrived.
+        #[cfg(not(test))]
+        fn pay_native(&mut self, to: AccountId, amount: Balance) -> bool {
+            self.transfer_native(to, amount).is_ok()
+        }
+
+        #[cfg(test)]
+        fn pay_native(&mut self, to: AccountId, amount: Balance) -> bool {
+            if mock::take_reentrant_withdrawer(to) {
+                // The recipient withdraws again before the payout arrives.
+                ink_env::test::set_caller::<ink_env::DefaultEnvironment>(to);
+                let reentry = self.withdraw_all();
+                mock::record_reentry(reentry);
+            }
+            self.transfer_native(to, amount).is_ok()
+        }
+
+        #[cfg(not(test))]
+        fn transfer_native(&self, to: AccountId, amount: Balance) -> Result<()> {
+            self.env()
+                .transfer(to, amount)
+                .map_err(|_| Error::NativeTransferFailed)
+        }
+
+        /// The off-chain engine panics on a transfer the contract cannot
+        /// cover, where a chain would fail it.
+        #[cfg(test)]
+        fn transfer_native(&self, to: AccountId, amount: Balance) -> Result<()> {
+            if amount > self.env().balance() {
+                return Err(Error::NativeTransferFailed);
+            }
+            self.env()
+                .transfer(to, amount)
+                .map_err(|_| Error::NativeTransferFailed)
+        }
+
+        #[cfg(not(test))]
+        fn block_timestamp(&self) -> u64 {
+            self.env().block_timestamp()
+        }
+
+        /// The off-chain engine only moves time along with `advance_block`,
+        /// so the tests set the timestamp through `mock`.
+        #[cfg(test)]
+        fn block_timestamp(&self) -> u64 {
+            mock::block_timestamp()
+        }
+
+        #[cfg(not(test))]
+        fn call_fee_oracle(&self, oracle: AccountId) -> Option<u16> {
+            build_call::<<Self as ::ink_lang::reflect::ContractEnv>::Env>()
+                .call_type(Call::new().callee(oracle).gas_limit(0))
+                .exec_input(ExecutionInput::new(Selector::new(GET_FEE_BPS_SELECTOR)))
+                .returns::<u16>()
+                .fire()
+                .ok()
+        }
+
+        #[cfg(test)]
+        fn call_fee_oracle(&self, oracle: AccountId) -> Option<u16> {
+            mock::oracle_response(oracle)
+        }
+
+        #[cfg(test)]
+        fn call_token_balance_of(&self, token: AccountId, _owner: AccountId) -> Result<Balance> {
+            mock::token_balance(token).ok_or(Error::RecoveryFailed)
+        }
+
+        #[cfg(test)]
+        fn call_token_transfer(&mut self, token: AccountId, _to: AccountId, _amount: Balance) -> Result<()> {
+            match mock::token_response(token) {
+                Some(true) => Ok(()),
+                _ => Err(Error::RecoveryFailed),
+            }
+        }
+
+        #[cfg(test)]
+        fn call_flash_borrower(
+            &mut self,
+            _initiator: AccountId,
+            receiver: AccountId,
+            _amount: Balance,
+            _fee: Balance,
+            _data: Vec<u8>,
+        ) -> Result<()> {
+            match mock::borrower_response(receiver) {
+                Some(Some(response)) if response == ON_FLASH_LOAN_SELECTOR => Ok(()),
+                _ => Err(Error::TransferCallbackFailed),
+            }
+        }
+
+    }
+
+    /// Unit tests.
+    #[cfg(test)]
+    mod tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+
+        type Event = <Erc20 as ::ink_lang::reflect::ContractEventBase>::Type;
+
+        use ink_lang as ink;
+
+        fn assert_transfer_event(
+            event: &ink_env::test::EmittedEvent,
+            expected_from: Option<AccountId>,
+            expected_to: Option<AccountId>,
+            expected_value: Balance,
+        ) {
+            let decoded_event = <Event as scale::Decode>::decode(&mut &event.data[..])
+                .expect("encountered invalid contract event data buffer");
+            if let Event::Transfer(Transfer { from, to, value }) = decoded_event {
+                assert_eq!(from, expected_from, "encountered invalid Transfer.from");
+                assert_eq!(to, expected_to, "encountered invalid Transfer.to");
+                assert_eq!(value, expected_value, "encountered invalid Trasfer.value");
+            } else {
+                panic!("encountered unexpected event kind: expected a Transfer event")
+            }
+            let expected_topics = vec![
+                encoded_into_hash(&PrefixedValue {
+                    value: b"Erc20::Transfer",
+                    prefix: b"",
+                }),
+                encoded_into_hash(&PrefixedValue {
+                    prefix: b"Erc20::Transfer::from",
+                    value: &expected_from,
+                }),
+                encoded_into_hash(&PrefixedValue {
+                    prefix: b"Erc20::Transfer::to",
+                    value: &expected_to,
+                }),
+                encoded_into_hash(&PrefixedValue {
+                    prefix: b"Erc20::Transfer::value",
+                    value: &expected_value,
+                }),
+            ];
+            for (n, (actual_topic, expected_topic)) in
+            event.topics.iter().zip(expected_topics).enumerate()
+            {
+                let topic = <Hash as scale::Decode>::decode(&mut &actual_topic[..])
+                    .expect("encountered invalid topic encoding");
+                assert_eq!(topic, expected_topic, "encountered invalid topic at {}", n);
+            }
+        }
+
+        /// Asserts that every topic of `event` matches `expected_topics`.
+        fn assert_topics(event: &ink_env::test::EmittedEvent, expected_topics: Vec<Hash>) {
+            assert_eq!(
+                event.topics.len(),
+                expected_topics.len(),
+                "encountered invalid number of topics"
+            );
+            for (n, (actual_topic, expected_topic)) in
+            event.topics.iter().zip(expected_topics).enumerate()
+            {
+                let topic = <Hash as scale::Decode>::decode(&mut &actual_topic[..])
+                    .expect("encountered invalid topic encoding");
+                assert_eq!(topic, expected_topic, "encountered invalid topic at {}", n);
+            }
+        }
+
+        /// Decodes a `Transfer` event and checks its topic layout.
+        fn decode_transfer(event: &ink_env::test::EmittedEvent) -> Transfer {
+            let decoded_event = <Event as scale::Decode>::decode(&mut &event.data[..])
+                .expect("encountered invalid contract event data buffer");
+            let transfer = if let Event::Transfer(transfer) = decoded_event {
+                transfer
+            } else {
+                panic!("encountered unexpected event kind: expected a Transfer event")
+            };
+            assert_topics(event, vec![
+                encoded_into_hash(&PrefixedValue {
+                    value: b"Erc20::Transfer",
+                    prefix: b"",
+                }),
+                encoded_into_hash(&PrefixedValue {
+                    prefix: b"Erc20::Transfer::from",
+                    value: &transfer.from,
+                }),
+                encoded_into_hash(&PrefixedValue {
+                    prefix: b"Erc20::Transfer::to",
+                    value: &transfer.to,
+                }),
+            ]);
+            transfer
+        }
+
+        /// Decodes an `Approval` event and checks its topic layout.
+        fn decode_approval(event: &ink_env::test::EmittedEvent) -> Approval {
+            let decoded_event = <Event as scale::Decode>::decode(&mut &event.data[..])
+                .expect("encountered invalid contract event data buffer");
+            let approval = if let Event::Approval(approval) = decoded_event {
+                approval
+            } else {
+                panic!("encountered unexpected event kind: expected an Approval event")
+            };
+            assert_topics(event, vec![
+                encoded_into_hash(&PrefixedValue {
+                    value: b"Erc20::Approval",
+                    prefix: b"",
+                }),
+                encoded_into_hash(&PrefixedValue {
+                    prefix: b"Erc20::Approval::owner",
+                    value: &approval.owner,
+                }),
+                encoded_into_hash(&PrefixedValue {
+                    prefix: b"Erc20::Approval::spender",
+                    value: &approval.spender,
+                }),
+            ]);
+            approval
+        }
+
+        /// Checks that the supply matches everything minted minus everything burned.
+        fn assert_supply_accounting(erc20: &Erc20) {
+            debug_assert_eq!(
+                erc20.total_minted() - erc20.total_burned(),
+                erc20.total_supply(),
+                "encountered inconsistent supply accounting"
+            );
+        }
+
+        /// Secret key of the permit signer used throughout the tests.
+        const PERMIT_SECRET: [u8; 32] = [0x42; 32];
+
+        /// The account controlled by `PERMIT_SECRET`.
+        fn permit_signer() -> AccountId {
+            let secp = secp256k1::Secp256k1::new();
+            let secret_key = secp256k1::SecretKey::from_slice(&PERMIT_SECRET)
+                .expect("encountered invalid secret key");
+            let public_key = secp256k1::PublicKey::from_secret_key(&secp, &secret_key);
+            let mut output = <Blake2x256 as ink_env::hash::HashOutput>::Type::default();
+            ink_env::hash_bytes::<Blake2x256>(&public_key.serialize(), &mut output);
+            AccountId::from(output)
+        }
+
+        /// Signs `message_hash` with `PERMIT_SECRET` in the recoverable format.
+        fn sign_permit(message_hash: [u8; 32]) -> [u8; 65] {
+            let secp = secp256k1::Secp256k1::new();
+            let secret_key = secp256k1::SecretKey::from_slice(&PERMIT_SECRET)
+                .expect("encountered invalid secret key");
+            let message = secp256k1::Message::from_slice(&message_hash)
+                .expect("encountered invalid message hash");
+            let (recovery_id, compact) = secp
+                .sign_ecdsa_recoverable(&message, &secret_key)
+                .serialize_compact();
+            let mut signature = [0u8; 65];
+            signature[..64].copy_from_slice(&compact);
+            signature[64] = recovery_id.to_i32() as u8;
+            signature
+        }
+
+        /// Makes `sender` the caller of all following messages.
+        fn set_sender(sender: AccountId) {
+            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(sender);
+        }
+
+        /// Gives the contract an account of its own, where the off-chain
+        /// engine would run it as Alice. Storage is kept per contract account,
+        /// so this has to come before the constructor.
+        fn set_contract_account() -> AccountId {
+            let contract = AccountId::from([0xc0; 32]);
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(contract);
+            contract
+        }
+
+        /// The default constructor does its job.
+        #[ink::test]
+        fn new_works() {
+            // Constructor works.
+            let _erc20 = Erc20::new(100);
+
+            // Transfer event triggered during initial construction.
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(1, emitted_events.len());
+
+            assert_transfer_event(
+                &emitted_events[0],
+                None,
+                Some(AccountId::from([0x01; 32])),
+                100,
+            );
+        }
+
+        /// The total supply was applied.
+        #[ink::test]
+        fn total_supply_works() {
+            // Constructor works.
+            let erc20 = Erc20::new(100);
+            // Transfer event triggered during initial construction.
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_transfer_event(
+                &emitted_events[0],
+                None,
+                Some(AccountId::from([0x01; 32])),
+                100,
+            );
+            // Get the token total supply.
+            assert_eq!(erc20.total_supply(), 100);
+        }
+
+        /// Get the actual balance of an account.
+        #[ink::test]
+        fn balance_of_works() {
+            // Constructor works
+            let erc20 = Erc20::new(100);
+            // Transfer event triggered during initial construction
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_transfer_event(
+                &emitted_events[0],
+                None,
+                Some(AccountId::from([0x01; 32])),
+                100,
+            );
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            // Alice owns all the tokens on deployment
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+            // Bob does not owns tokens
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn transfer_works() {
+            // Constructor works.
+            let mut erc20 = Erc20::new(100);
+            // Transfer event triggered during initial construction.
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            // Alice transfers 10 tokens to Bob.
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            // Bob owns 10 tokens.
+            assert_eq!(erc20.balance_of(accounts.bob), 10);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 2);
+            // Check first transfer event related to ERC-20 instantiation.
+            assert_transfer_event(
+                &emitted_events[0],
+                None,
+                Some(AccountId::from([0x01; 32])),
+                100,
+            );
+            // Check the second transfer event relating to the actual trasfer.
+            assert_transfer_event(
+                &emitted_events[1],
+                Some(AccountId::from([0x01; 32])),
+                Some(AccountId::from([0x02; 32])),
+                10,
+            );
+        }
+
+        #[ink::test]
+        fn invalid_transfer_should_fail() {
+            // Constructor works.
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            // Set Bob as caller.
+            set_sender(accounts.bob);
+
+            // Bob fails to transfers 10 tokens to Eve.
+            assert_eq!(
+                erc20.transfer(accounts.eve, 10),
+                Err(Error::InsufficientBalance)
+            );
+            // Alice owns all the tokens.
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.balance_of(accounts.eve), 0);
+
+            // Transfer event triggered during initial construction.
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 1);
+            assert_transfer_event(
+                &emitted_events[0],
+                None,
+                Some(AccountId::from([0x01; 32])),
+                100,
+            );
+        }
+
+        #[ink::test]
+        fn transfer_from_works() {
+            // Constructor works.
+            let mut erc20 = Erc20::new(100);
+            // Transfer event triggered during initial construction.
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            // Bob fails to transfer tokens owned by Alice.
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.eve, 10),
+                Err(Error::InsufficientApproval)
+            );
+            // Alice approves Bob for token transfers on her behalf.
+            assert_eq!(erc20.approve(accounts.bob, 10), Ok(()));
+
+            // The approve event takes place.
+            assert_eq!(ink_env::test::recorded_events().count(), 2);
+
+            // Set Bob as caller.
+            set_sender(accounts.bob);
+
+            // Bob transfers tokens from Alice to Eve.
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.eve, 10),
+                Ok(())
+            );
+            // Eve owns tokens.
+            assert_eq!(erc20.balance_of(accounts.eve), 10);
+
+            // Check all transfer events that happened during the previous calls:
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 5);
+            assert_transfer_event(
+                &emitted_events[0],
+                None,
+                Some(AccountId::from([0x01; 32])),
+                100,
+            );
+            // The second event `emitted_events[1]` is an Approve event that we skip checking.
+            assert_transfer_event(
+                &emitted_events[2],
+                Some(AccountId::from([0x01; 32])),
+                Some(AccountId::from([0x05; 32])),
+                10,
+            );
+        }
+
+        #[ink::test]
+        fn allowance_must_not_change_on_failed_transfer() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            // Alice approves Bob for token transfers on her behalf.
+            let alice_balance = erc20.balance_of(accounts.alice);
+            let initial_allowance = alice_balance + 2;
+            assert_eq!(erc20.approve(accounts.bob, initial_allowance), Ok(()));
+
+            // Set Bob as caller.
+            set_sender(accounts.bob);
+
+            // Bob tries to transfer tokens from Alice to Eve.
+            let emitted_events_before = ink_env::test::recorded_events();
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.eve, alice_balance + 1),
+                Err(Error::InsufficientBalance)
+            );
+            // Allowance must have stayed the same
+            assert_eq!(
+                erc20.allowance(accounts.alice, accounts.bob),
+                initial_allowance
+            );
+            // No more events must have been emitted
+            let emitted_events_after = ink_env::test::recorded_events();
+            assert_eq!(emitted_events_before.count(), emitted_events_after.count());
+        }
+
+        #[ink::test]
+        fn transfer_overflow_should_fail() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            // Put Bob's balance right at the top of the range.
+            erc20.balances.insert(accounts.bob, &(Balance::MAX - 5));
+
+            // Alice's transfer would push Bob past `Balance::MAX`.
+            assert_eq!(erc20.transfer(accounts.bob, 10), Err(Error::Overflow));
+            // Balances must be untouched.
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+            assert_eq!(erc20.balance_of(accounts.bob), Balance::MAX - 5);
+
+            // Only the instantiation transfer event was emitted.
+            assert_eq!(ink_env::test::recorded_events().count(), 1);
+        }
+
+        #[ink::test]
+        fn mint_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            // Mint 50 new tokens to Bob.
+            assert_eq!(erc20.mint(accounts.bob, 50), Ok(()));
+            // Both the total supply and Bob's balance grew by 50.
+            assert_eq!(erc20.total_supply(), 150);
+            assert_eq!(erc20.balance_of(accounts.bob), 50);
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+
+            // The mint is reported as a transfer from nobody.
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 2);
+            assert_transfer_event(
+                &emitted_events[1],
+                None,
+                Some(AccountId::from([0x02; 32])),
+                50,
+            );
+        }
+
+        #[ink::test]
+        fn mint_overflow_should_fail() {
+            let mut erc20 = Erc20::new(Balance::MAX);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            // The total supply is already at its maximum.
+            assert_eq!(erc20.mint(accounts.bob, 1), Err(Error::Overflow));
+            assert_eq!(erc20.total_supply(), Balance::MAX);
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn burn_full_balance_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            // Alice burns everything she owns.
+            assert_eq!(erc20.burn(100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 0);
+            assert_eq!(erc20.total_supply(), 0);
+
+            // The burn is reported as a transfer to nobody.
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 2);
+            assert_transfer_event(
+                &emitted_events[1],
+                Some(AccountId::from([0x01; 32])),
+                None,
+                100,
+            );
+        }
+
+        #[ink::test]
+        fn burn_more_than_balance_should_fail() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.burn(101), Err(Error::InsufficientBalance));
+            // Nothing changed and no event was emitted.
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+            assert_eq!(erc20.total_supply(), 100);
+            assert_eq!(ink_env::test::recorded_events().count(), 1);
+        }
+
+        #[ink::test]
+        fn burn_zero_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.burn(0), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+            assert_eq!(erc20.total_supply(), 100);
+        }
+
+        #[ink::test]
+        fn burn_from_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            // Alice approves Bob to spend 30 of her tokens.
+            assert_eq!(erc20.approve(accounts.bob, 30), Ok(()));
+
+            // Bob burns 20 of Alice's tokens.
+            set_sender(accounts.bob);
+            assert_eq!(erc20.burn_from(accounts.alice, 20), Ok(()));
+
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 10);
+            assert_eq!(erc20.balance_of(accounts.alice), 80);
+            assert_eq!(erc20.total_supply(), 80);
+
+            // Instantiation, approval, burn and the reduced approval.
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 4);
+            assert_transfer_event(
+                &emitted_events[2],
+                Some(AccountId::from([0x01; 32])),
+                None,
+                20,
+            );
+        }
+
+        #[ink::test]
+        fn burn_from_checks_approval_before_balance() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.approve(accounts.bob, 200), Ok(()));
+
+            set_sender(accounts.bob);
+            // Both the approval and the balance are too small.
+            assert_eq!(
+                erc20.burn_from(accounts.alice, 300),
+                Err(Error::InsufficientApproval)
+            );
+            // Only the balance is too small.
+            assert_eq!(
+                erc20.burn_from(accounts.alice, 150),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 200);
+            assert_eq!(erc20.total_supply(), 100);
+        }
+
+        #[ink::test]
+        fn transfer_from_emits_remaining_allowance() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            // Alice approves Bob for 100 tokens and Bob spends 30 of them.
+            assert_eq!(erc20.approve(accounts.bob, 100), Ok(()));
+            set_sender(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.eve, 30),
+                Ok(())
+            );
+
+            // The last event must report the allowance that is actually stored.
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 5);
+            let approval = decode_approval(&emitted_events[4]);
+            assert_eq!(approval.owner, accounts.alice);
+            assert_eq!(approval.spender, accounts.bob);
+            assert_eq!(approval.value, 70);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 70);
+        }
+
+        #[ink::test]
+        fn transfer_from_full_allowance_leaves_zero() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.approve(accounts.bob, 40), Ok(()));
+            set_sender(accounts.bob);
+            // Bob spends exactly his whole allowance.
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.eve, 40),
+                Ok(())
+            );
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(erc20.balance_of(accounts.eve), 40);
+
+            // Any further spend is rejected.
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.eve, 1),
+                Err(Error::InsufficientApproval)
+            );
+        }
+
+        #[ink::test]
+        fn infinite_allowance_is_never_spent() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.approve(accounts.bob, Balance::MAX), Ok(()));
+            set_sender(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.eve, 10),
+                Ok(())
+            );
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.eve, 20),
+                Ok(())
+            );
+
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), Balance::MAX);
+            assert_eq!(erc20.balance_of(accounts.eve), 30);
+            // Instantiation, approval and two `Transfer` + `TransferFrom` pairs,
+            // without any follow-up `Approval` events.
+            assert_eq!(ink_env::test::recorded_events().count(), 6);
+        }
+
+        #[ink::test]
+        fn increase_allowance_from_zero_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            // A pair without an entry reads as zero.
+            assert_eq!(erc20.allowances.get((accounts.alice, accounts.bob)), None);
+            assert_eq!(erc20.increase_allowance(accounts.bob, 15), Ok(()));
+            assert_eq!(erc20.allowances.get((accounts.alice, accounts.bob)), Some(15));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 15);
+            assert_eq!(erc20.increase_allowance(accounts.bob, 5), Ok(()));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 20);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 3);
+            let approval = decode_approval(&emitted_events[1]);
+            assert_eq!(approval.owner, accounts.alice);
+            assert_eq!(approval.spender, accounts.bob);
+            assert_eq!(approval.value, 15);
+        }
+
+        #[ink::test]
+        fn decrease_allowance_to_zero_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.approve(accounts.bob, 20), Ok(()));
+            assert_eq!(erc20.decrease_allowance(accounts.bob, 20), Ok(()));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(ink_env::test::recorded_events().count(), 3);
+        }
+
+        #[ink::test]
+        fn decrease_allowance_below_zero_should_fail() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.approve(accounts.bob, 20), Ok(()));
+            assert_eq!(
+                erc20.decrease_allowance(accounts.bob, 21),
+                Err(Error::InsufficientApproval)
+            );
+            // The allowance is untouched and no event was emitted.
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 20);
+            assert_eq!(ink_env::test::recorded_events().count(), 2);
+        }
+
+        #[ink::test]
+        fn metadata_works() {
+            let erc20 = Erc20::new_with_metadata(
+                100,
+                Some(String::from("Vege Token")),
+                Some(String::from("VEGE")),
+                12,
+            );
+            assert_eq!(erc20.token_name(), Some(String::from("Vege Token")));
+            assert_eq!(erc20.token_symbol(), Some(String::from("VEGE")));
+            assert_eq!(erc20.token_decimals(), 12);
+            assert_eq!(erc20.total_supply(), 100);
+        }
+
+        #[ink::test]
+        fn new_uses_default_metadata() {
+            let erc20 = Erc20::new(100);
+            assert_eq!(erc20.token_name(), None);
+            assert_eq!(erc20.token_symbol(), None);
+            assert_eq!(erc20.token_decimals(), 18);
+        }
+
+        #[ink::test]
+        fn absent_mapping_entries_read_as_zero() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            // Nothing was ever stored for Bob or the Alice/Bob pair.
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 0);
+
+            // Overwriting existing entries keeps only the latest value.
+            assert_eq!(erc20.approve(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.approve(accounts.bob, 3), Ok(()));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 3);
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.transfer(accounts.bob, 5), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 15);
+            assert_eq!(erc20.balance_of(accounts.alice), 85);
+        }
+
+        #[ink::test]
+        fn deployer_is_owner() {
+            let erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.owner(), accounts.alice);
+        }
+
+        #[ink::test]
+        fn transfer_ownership_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            // Nominating Bob does not hand over ownership yet.
+            assert_eq!(erc20.transfer_ownership(accounts.bob), Ok(()));
+            assert_eq!(erc20.owner(), accounts.alice);
+            assert_eq!(erc20.pending_owner(), Some(accounts.bob));
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.accept_ownership(), Ok(()));
+            assert_eq!(erc20.owner(), accounts.bob);
+            assert_eq!(erc20.pending_owner(), None);
+            assert_eq!(ink_env::test::recorded_events().count(), 3);
+
+            // Alice is no longer allowed to move ownership around.
+            set_sender(accounts.alice);
+            assert_eq!(
+                erc20.transfer_ownership(accounts.alice),
+                Err(Error::NotOwner)
+            );
+
+            // Bob now can.
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer_ownership(accounts.charlie), Ok(()));
+            set_sender(accounts.charlie);
+            assert_eq!(erc20.accept_ownership(), Ok(()));
+            assert_eq!(erc20.owner(), accounts.charlie);
+        }
+
+        #[ink::test]
+        fn only_pending_owner_can_accept_ownership() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.accept_ownership(), Err(Error::NotPendingOwner));
+
+            set_sender(accounts.alice);
+            assert_eq!(erc20.transfer_ownership(accounts.bob), Ok(()));
+            assert_eq!(erc20.accept_ownership(), Err(Error::NotPendingOwner));
+            set_sender(accounts.charlie);
+            assert_eq!(erc20.accept_ownership(), Err(Error::NotPendingOwner));
+            assert_eq!(erc20.owner(), accounts.alice);
+            assert_eq!(erc20.pending_owner(), Some(accounts.bob));
+        }
+
+        #[ink::test]
+        fn renounce_ownership_leaves_no_owner() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.renounce_ownership(), Err(Error::NotOwner));
+
+            set_sender(accounts.alice);
+            assert_eq!(erc20.transfer_ownership(accounts.bob), Ok(()));
+            assert_eq!(erc20.renounce_ownership(), Ok(()));
+            assert_eq!(erc20.owner(), AccountId::from([0u8; 32]));
+            assert_eq!(erc20.pause(), Err(Error::NotOwner));
+
+            // The earlier nomination is void.
+            set_sender(accounts.bob);
+            assert_eq!(erc20.accept_ownership(), Err(Error::NotPendingOwner));
+        }
+
+        #[ink::test]
+        fn non_owner_cannot_transfer_ownership() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            set_sender(accounts.bob);
+            assert_eq!(
+                erc20.transfer_ownership(accounts.bob),
+                Err(Error::NotOwner)
+            );
+            assert_eq!(erc20.owner(), accounts.alice);
+        }
+
+        #[ink::test]
+        fn non_minter_mint_should_fail() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.mint(accounts.bob, 50), Err(Error::MissingRole));
+            assert_eq!(erc20.total_supply(), 100);
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn burn_from_any_is_owner_only() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.transfer(accounts.bob, 40), Ok(()));
+
+            // The owner burns Bob's tokens without any allowance.
+            assert_eq!(erc20.burn_from_any(accounts.bob, 15), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 25);
+            assert_eq!(erc20.total_supply(), 85);
+
+            // Bob cannot do the same to Alice.
+            set_sender(accounts.bob);
+            assert_eq!(
+                erc20.burn_from_any(accounts.alice, 15),
+                Err(Error::NotOwner)
+            );
+            assert_eq!(erc20.balance_of(accounts.alice), 60);
+        }
+
+        #[ink::test]
+        fn pause_blocks_transfers_and_approvals() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.approve(accounts.bob, 50), Ok(()));
+
+            assert_eq!(erc20.pause(), Ok(()));
+            assert!(erc20.is_paused());
+
+            assert_eq!(erc20.transfer(accounts.bob, 10), Err(Error::Paused));
+            assert_eq!(erc20.approve(accounts.charlie, 10), Err(Error::Paused));
+            set_sender(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 10),
+                Err(Error::Paused)
+            );
+            // Even without a sufficient allowance.
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 51),
+                Err(Error::Paused)
+            );
+            set_sender(accounts.charlie);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.charlie, 1),
+                Err(Error::Paused)
+            );
+            // Nothing moved while paused.
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 50);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.charlie), 0);
+
+            set_sender(accounts.alice);
+            assert_eq!(erc20.unpause(), Ok(()));
+            assert!(!erc20.is_paused());
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 10);
+        }
+
+        #[ink::test]
+        fn non_owner_cannot_pause() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.pause(), Err(Error::NotOwner));
+            assert!(!erc20.is_paused());
+        }
+
+        #[ink::test]
+        fn mint_up_to_cap_works() {
+            let mut erc20 = Erc20::new_capped(100, 150);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.cap(), Some(150));
+
+            assert_eq!(erc20.mint(accounts.bob, 50), Ok(()));
+            assert_eq!(erc20.total_supply(), 150);
+        }
+
+        #[ink::test]
+        fn mint_over_cap_should_fail() {
+            let mut erc20 = Erc20::new_capped(100, 150);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.mint(accounts.bob, 51), Err(Error::CapExceeded));
+            assert_eq!(erc20.total_supply(), 100);
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn uncapped_mint_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.cap(), None);
+
+            assert_eq!(erc20.mint(accounts.bob, 1_000_000), Ok(()));
+            assert_eq!(erc20.total_supply(), 1_000_100);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "initial supply exceeds the cap")]
+        fn new_capped_rejects_initial_supply_over_cap() {
+            let _erc20 = Erc20::new_capped(151, 150);
+        }
+
+        #[ink::test]
+        fn batch_transfer_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(
+                erc20.batch_transfer(vec![
+                    (accounts.bob, 10),
+                    (accounts.charlie, 20),
+                    (accounts.eve, 30),
+                ]),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.alice), 40);
+            assert_eq!(erc20.balance_of(accounts.bob), 10);
+            assert_eq!(erc20.balance_of(accounts.charlie), 20);
+            assert_eq!(erc20.balance_of(accounts.eve), 30);
+            // One `Transfer` event per recipient.
+            assert_eq!(ink_env::test::recorded_events().count(), 4);
+        }
+
+        #[ink::test]
+        fn batch_transfer_over_balance_should_fail() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            // Each single transfer fits the balance, their sum does not.
+            assert_eq!(
+                erc20.batch_transfer(vec![(accounts.bob, 60), (accounts.charlie, 60)]),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.balance_of(accounts.charlie), 0);
+            assert_eq!(ink_env::test::recorded_events().count(), 1);
+        }
+
+        #[ink::test]
+        fn batch_transfer_too_large_should_fail() {
+            let mut erc20 = Erc20::new(1000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            let recipients = vec![(accounts.bob, 1); MAX_BATCH_LEN + 1];
+            assert_eq!(erc20.batch_transfer(recipients), Err(Error::BatchTooLarge));
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn self_transfer_keeps_balance() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.transfer(accounts.alice, 40), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+            assert_eq!(erc20.total_supply(), 100);
+
+            // The transfer is still reported.
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 2);
+            assert_transfer_event(
+                &emitted_events[1],
+                Some(AccountId::from([0x01; 32])),
+                Some(AccountId::from([0x01; 32])),
+                40,
+            );
+
+            // Sending more than is held still fails.
+            assert_eq!(
+                erc20.transfer(accounts.alice, 101),
+                Err(Error::InsufficientBalance)
+            );
+        }
+
+        #[ink::test]
+        fn zero_address_is_rejected() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let zero = AccountId::from([0u8; 32]);
+
+            assert_eq!(erc20.transfer(zero, 10), Err(Error::ZeroAddress));
+            assert_eq!(erc20.approve(zero, 10), Err(Error::ZeroAddress));
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+            assert_eq!(erc20.allowance(accounts.alice, zero), 0);
+
+            // Regular accounts are unaffected.
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.approve(accounts.bob, 10), Ok(()));
+        }
+
+        #[ink::test]
+        fn zero_address_check_can_be_disabled() {
+            let mut erc20 = Erc20::new_with_zero_address_check(100, false);
+            let zero = AccountId::from([0u8; 32]);
+
+            assert_eq!(erc20.transfer(zero, 10), Ok(()));
+            assert_eq!(erc20.balance_of(zero), 10);
+        }
+
+        /// Moves tokens knowing nothing about `Erc20` beyond its PSP22 interface.
+        ///
+        /// ink! trait messages return associated types, pinned down here.
+        fn psp22_transfer<T>(token: &mut T, to: AccountId, value: Balance) -> Result<()>
+        where
+            T: PSP22<transferOutput = Result<()>>,
+        {
+            token.transfer(to, value, Vec::new())
+        }
+
+        #[ink::test]
+        fn psp22_interface_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(psp22_transfer(&mut erc20, accounts.bob, 10), Ok(()));
+            assert_eq!(PSP22::balance_of(&erc20, accounts.bob), 10);
+            assert_eq!(PSP22::total_supply(&erc20), 100);
+
+            assert_eq!(PSP22::approve(&mut erc20, accounts.bob, 30), Ok(()));
+            assert_eq!(PSP22::increase_allowance(&mut erc20, accounts.bob, 5), Ok(()));
+            assert_eq!(PSP22::decrease_allowance(&mut erc20, accounts.bob, 15), Ok(()));
+            assert_eq!(PSP22::allowance(&erc20, accounts.alice, accounts.bob), 20);
+
+            set_sender(accounts.bob);
+            assert_eq!(
+                PSP22::transfer_from(&mut erc20, accounts.alice, accounts.eve, 20, Vec::new()),
+                Ok(())
+            );
+            // The inherent getters see the same state.
+            assert_eq!(erc20.balance_of(accounts.eve), 20);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn transfer_with_data_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(
+                erc20.transfer_with_data(accounts.bob, 10, vec![0xde, 0xad, 0xbe, 0xef]),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 10);
+
+            // Instantiation, the plain transfer and the one carrying the payload.
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 3);
+            assert_transfer_event(
+                &emitted_events[1],
+                Some(AccountId::from([0x01; 32])),
+                Some(AccountId::from([0x02; 32])),
+                10,
+            );
+            let decoded_event = <Event as scale::Decode>::decode(&mut &emitted_events[2].data[..])
+                .expect("encountered invalid contract event data buffer");
+            if let Event::TransferWithData(TransferWithData { from, to, value, data }) = decoded_event {
+                assert_eq!(from, Some(accounts.alice), "encountered invalid TransferWithData.from");
+                assert_eq!(to, Some(accounts.bob), "encountered invalid TransferWithData.to");
+                assert_eq!(value, 10, "encountered invalid TransferWithData.value");
+                assert_eq!(data, vec![0xde, 0xad, 0xbe, 0xef], "encountered invalid TransferWithData.data");
+            } else {
+                panic!("encountered unexpected event kind: expected a TransferWithData event")
+            }
+        }
+
+        #[ink::test]
+        fn allowance_of_unapproved_pair_is_zero() {
+            let erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(erc20.allowance(accounts.bob, accounts.alice), 0);
+            assert_eq!(erc20.balance_of(accounts.django), 0);
+        }
+
+        #[ink::test]
+        fn transfer_from_decrements_allowance() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.approve(accounts.bob, 25), Ok(()));
+            set_sender(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.charlie, 10),
+                Ok(())
+            );
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 15);
+            assert_eq!(erc20.balance_of(accounts.alice), 90);
+            assert_eq!(erc20.balance_of(accounts.charlie), 10);
+            // Instantiation, approval, transfer, transfer-from and the new approval.
+            assert_eq!(ink_env::test::recorded_events().count(), 5);
+        }
+
+        #[ink::test]
+        fn transfer_event_fields_are_exact() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.transfer(accounts.bob, 7), Ok(()));
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let transfer = decode_transfer(&emitted_events[1]);
+            assert_eq!(transfer.from, Some(accounts.alice));
+            assert_eq!(transfer.to, Some(accounts.bob));
+            assert_eq!(transfer.value, 7);
+        }
+
+        #[ink::test]
+        fn mint_and_burn_event_fields_are_exact() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.mint(accounts.bob, 12), Ok(()));
+            assert_eq!(erc20.burn(30), Ok(()));
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 3);
+            let minted = decode_transfer(&emitted_events[1]);
+            assert_eq!(minted.from, None);
+            assert_eq!(minted.to, Some(accounts.bob));
+            assert_eq!(minted.value, 12);
+            let burned = decode_transfer(&emitted_events[2]);
+            assert_eq!(burned.from, Some(accounts.alice));
+            assert_eq!(burned.to, None);
+            assert_eq!(burned.value, 30);
+        }
+
+        #[ink::test]
+        fn approval_event_fields_are_exact() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.approve(accounts.bob, 40), Ok(()));
+            assert_eq!(erc20.increase_allowance(accounts.bob, 2), Ok(()));
+            assert_eq!(erc20.decrease_allowance(accounts.bob, 12), Ok(()));
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let values = emitted_events[1..]
+                .iter()
+                .map(|event| {
+                    let approval = decode_approval(event);
+                    assert_eq!(approval.owner, accounts.alice);
+                    assert_eq!(approval.spender, accounts.bob);
+                    approval.value
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(values, vec![40, 42, 30]);
+        }
+
+        #[ink::test]
+        fn transfer_topics_include_sender_and_recipient() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.transfer(accounts.bob, 5), Ok(()));
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let event = &emitted_events[1];
+            // The signature followed by `from` and `to`.
+            assert_eq!(event.topics.len(), 3);
+            let to_topic = <Hash as scale::Decode>::decode(&mut &event.topics[2][..])
+                .expect("encountered invalid topic encoding");
+            assert_eq!(
+                to_topic,
+                encoded_into_hash(&PrefixedValue {
+                    prefix: b"Erc20::Transfer::to",
+                    value: &Some(accounts.bob),
+                })
+            );
+            decode_transfer(event);
+        }
+
+        #[ink::test]
+        fn permit_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let owner = permit_signer();
+            let deadline = u64::MAX;
+
+            let hash = erc20.permit_hash(owner, accounts.bob, 50, 0, deadline);
+            let signature = sign_permit(hash);
+
+            // Anyone may submit the permit on behalf of the owner.
+            set_sender(accounts.charlie);
+            assert_eq!(
+                erc20.permit(owner, accounts.bob, 50, deadline, signature),
+                Ok(())
+            );
+            assert_eq!(erc20.allowance(owner, accounts.bob), 50);
+            assert_eq!(erc20.nonce_of(owner), 1);
+
+            // The same signature cannot be replayed.
+            assert_eq!(
+                erc20.permit(owner, accounts.bob, 50, deadline, signature),
+                Err(Error::InvalidSignature)
+            );
+        }
+
+        #[ink::test]
+        fn permit_with_tampered_value_should_fail() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let owner = permit_signer();
+            let deadline = u64::MAX;
+
+            let hash = erc20.permit_hash(owner, accounts.bob, 50, 0, deadline);
+            let signature = sign_permit(hash);
+
+            assert_eq!(
+                erc20.permit(owner, accounts.bob, 60, deadline, signature),
+                Err(Error::InvalidSignature)
+            );
+            assert_eq!(erc20.allowance(owner, accounts.bob), 0);
+            assert_eq!(erc20.nonce_of(owner), 0);
+        }
+
+        #[ink::test]
+        fn expired_permit_should_fail() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let owner = permit_signer();
+
+            mock::set_block_timestamp(1_000);
+            let hash = erc20.permit_hash(owner, accounts.bob, 50, 0, 999);
+            let signature = sign_permit(hash);
+
+            assert_eq!(
+                erc20.permit(owner, accounts.bob, 50, 999, signature),
+                Err(Error::PermitExpired)
+            );
+        }
+
+        #[ink::test]
+        fn snapshot_keeps_historical_balances() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.snapshot(), Ok(1));
+
+            assert_eq!(erc20.transfer(accounts.bob, 20), Ok(()));
+            assert_eq!(erc20.mint(accounts.charlie, 5), Ok(()));
+            assert_eq!(erc20.snapshot(), Ok(2));
+            assert_eq!(erc20.transfer(accounts.bob, 30), Ok(()));
+
+            // Current values.
+            assert_eq!(erc20.balance_of(accounts.alice), 40);
+            assert_eq!(erc20.balance_of(accounts.bob), 60);
+            assert_eq!(erc20.total_supply(), 105);
+
+            // As of the first snapshot.
+            assert_eq!(erc20.balance_of_at(accounts.alice, 1), 90);
+            assert_eq!(erc20.balance_of_at(accounts.bob, 1), 10);
+            assert_eq!(erc20.balance_of_at(accounts.charlie, 1), 0);
+            assert_eq!(erc20.total_supply_at(1), 100);
+
+            // As of the second snapshot.
+            assert_eq!(erc20.balance_of_at(accounts.alice, 2), 70);
+            assert_eq!(erc20.balance_of_at(accounts.bob, 2), 30);
+            assert_eq!(erc20.balance_of_at(accounts.charlie, 2), 5);
+            assert_eq!(erc20.total_supply_at(2), 105);
+        }
+
+        #[ink::test]
+        fn snapshots_stay_readable_after_many_changes() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            // Bob's balance changes after each of 300 snapshots.
+            for id in 1..=300u64 {
+                assert_eq!(erc20.snapshot(), Ok(id));
+                assert_eq!(erc20.transfer(accounts.bob, 1), Ok(()));
+            }
+            assert_eq!(erc20.balance_of_at(accounts.bob, 1), 0);
+            assert_eq!(erc20.balance_of_at(accounts.bob, 150), 149);
+            assert_eq!(erc20.balance_of_at(accounts.bob, 300), 299);
+            assert_eq!(erc20.balance_of_at(accounts.alice, 37), 964);
+            assert_eq!(erc20.total_supply_at(200), 1_000);
+            assert_eq!(erc20.balance_of(accounts.bob), 300);
+        }
+
+        #[ink::test]
+        fn non_owner_cannot_snapshot() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.snapshot(), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn delegated_votes_follow_balance() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.delegates(accounts.alice), AccountId::from([0u8; 32]));
+
+            // Block 0: Alice delegates to Bob.
+            erc20.delegate(accounts.bob);
+            assert_eq!(erc20.delegates(accounts.alice), accounts.bob);
+            assert_eq!(erc20.get_votes(accounts.bob), 100);
+            assert_eq!(erc20.get_votes(accounts.alice), 0);
+
+            // Block 1: Alice transfers tokens to Charlie who votes for himself.
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.transfer(accounts.charlie, 30), Ok(()));
+            assert_eq!(erc20.get_votes(accounts.bob), erc20.balance_of(accounts.alice));
+            set_sender(accounts.charlie);
+            erc20.delegate(accounts.charlie);
+            assert_eq!(erc20.get_votes(accounts.charlie), 30);
+
+            // Block 2: Charlie sends some tokens back to Alice.
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.transfer(accounts.alice, 10), Ok(()));
+            assert_eq!(erc20.get_votes(accounts.bob), 80);
+            assert_eq!(erc20.get_votes(accounts.charlie), 20);
+
+            // Historical voting power.
+            assert_eq!(erc20.get_past_votes(accounts.bob, 0), 100);
+            assert_eq!(erc20.get_past_votes(accounts.bob, 1), 70);
+            assert_eq!(erc20.get_past_votes(accounts.charlie, 0), 0);
+            assert_eq!(erc20.get_past_votes(accounts.charlie, 1), 30);
+        }
+
+        #[ink::test]
+        fn vote_checkpoints_coalesce_within_a_block() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            set_sender(accounts.bob);
+            erc20.delegate(accounts.bob);
+            set_sender(accounts.alice);
+
+            // Dust in 300 blocks, several times per block.
+            for block in 1..=300u32 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+                for _ in 0..3 {
+                    assert_eq!(erc20.transfer(accounts.bob, 1), Ok(()));
+                }
+                assert_eq!(erc20.vote_checkpoint_count.get(accounts.bob), Some(block));
+            }
+            assert_eq!(erc20.get_votes(accounts.bob), 900);
+            assert_eq!(erc20.get_past_votes(accounts.bob, 0), 0);
+            assert_eq!(erc20.get_past_votes(accounts.bob, 1), 3);
+            assert_eq!(erc20.get_past_votes(accounts.bob, 150), 450);
+            assert_eq!(erc20.get_past_votes(accounts.bob, 1_000), 900);
+
+            // The delegatee can still move tokens and votes.
+            set_sender(accounts.bob);
+            erc20.delegate(accounts.charlie);
+            assert_eq!(erc20.get_votes(accounts.charlie), 900);
+            assert_eq!(erc20.transfer(accounts.alice, 900), Ok(()));
+            assert_eq!(erc20.get_votes(accounts.charlie), 0);
+        }
+
+        #[ink::test]
+        fn redelegation_moves_votes() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            erc20.delegate(accounts.bob);
+            erc20.delegate(accounts.charlie);
+            assert_eq!(erc20.get_votes(accounts.bob), 0);
+            assert_eq!(erc20.get_votes(accounts.charlie), 100);
+
+            // Minting to and burning from a delegator updates its delegate.
+            assert_eq!(erc20.mint(accounts.alice, 20), Ok(()));
+            assert_eq!(erc20.burn(50), Ok(()));
+            assert_eq!(erc20.get_votes(accounts.charlie), 70);
+        }
+
+        #[ink::test]
+        fn transfer_and_call_to_plain_account_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.transfer_and_call(accounts.bob, 10, Vec::new()), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 10);
+        }
+
+        #[ink::test]
+        fn transfer_and_call_to_accepting_receiver_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            mock::set_receiver_response(accounts.django, Some(ON_TOKEN_RECEIVED_SELECTOR));
+
+            assert_eq!(erc20.transfer_and_call(accounts.django, 10, vec![1, 2]), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.django), 10);
+        }
+
+        #[ink::test]
+        fn transfer_and_call_to_rejecting_receiver_should_fail() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            // A receiver answering with the wrong value.
+            mock::set_receiver_response(accounts.django, Some([0x00; 4]));
+            assert_eq!(
+                erc20.transfer_and_call(accounts.django, 10, Vec::new()),
+                Err(Error::TransferCallbackFailed)
+            );
+
+            // A receiver that traps.
+            mock::set_receiver_response(accounts.frank, None);
+            assert_eq!(
+                erc20.transfer_and_call(accounts.frank, 10, Vec::new()),
+                Err(Error::TransferCallbackFailed)
+            );
+        }
+
+        #[ink::test]
+        fn repaid_flash_loan_works() {
+            // 1% fee on flash loans.
+            let mut erc20 = Erc20::new_with_flash_fee(1000, 100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.max_flash_loan(), Balance::MAX - 1000);
+            assert_eq!(erc20.flash_fee(500), 5);
+
+            // The borrower already holds enough to cover the fee.
+            assert_eq!(erc20.transfer(accounts.django, 10), Ok(()));
+            mock::set_borrower_response(accounts.django, Some(ON_FLASH_LOAN_SELECTOR));
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.flash_mint(accounts.django, 500, Vec::new()), Ok(()));
+
+            // The loan is gone again and the fee went to the owner.
+            assert_eq!(erc20.total_supply(), 1000);
+            assert_eq!(erc20.balance_of(accounts.django), 5);
+            assert_eq!(erc20.balance_of(accounts.alice), 995);
+        }
+
+        #[ink::test]
+        fn flash_fee_goes_to_owner_untouched_by_transfer_rules() {
+            let mut erc20 = Erc20::new_with_flash_fee(1000, 100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.transfer(accounts.django, 10), Ok(()));
+            assert_eq!(erc20.set_treasury(accounts.eve), Ok(()));
+            assert_eq!(erc20.set_fee_bps(5_000), Ok(()));
+            // A transfer of the fee would need the timelock.
+            assert_eq!(erc20.set_timelock(Some(1), 1_000), Ok(()));
+            mock::set_borrower_response(accounts.django, Some(ON_FLASH_LOAN_SELECTOR));
+
+            assert_eq!(erc20.flash_mint(accounts.django, 500, Vec::new()), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 990 + erc20.flash_fee(500));
+            assert_eq!(erc20.balance_of(accounts.eve), 0);
+            assert_eq!(erc20.balance_of(accounts.django), 5);
+            assert_eq!(erc20.total_supply(), 1000);
+        }
+
+        #[ink::test]
+        fn unpaid_flash_loan_should_fail() {
+            let mut erc20 = Erc20::new_with_flash_fee(1000, 100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            // The borrower has nothing to pay the fee with.
+            mock::set_borrower_response(accounts.django, Some(ON_FLASH_LOAN_SELECTOR));
+            assert_eq!(
+                erc20.flash_mint(accounts.django, 500, Vec::new()),
+                Err(Error::FlashLoanNotRepaid)
+            );
+
+            // A borrower rejecting the loan.
+            mock::set_borrower_response(accounts.frank, Some([0x00; 4]));
+            assert_eq!(
+                erc20.flash_mint(accounts.frank, 500, Vec::new()),
+                Err(Error::TransferCallbackFailed)
+            );
+        }
+
+        #[ink::test]
+        fn transfer_fee_goes_to_treasury() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            // 2.5% fee.
+            let mut erc20 = Erc20::new_with_fee(1000, 250, accounts.charlie);
+            assert_eq!(erc20.fee_bps(), 250);
+            assert_eq!(erc20.treasury(), Some(accounts.charlie));
+
+            assert_eq!(erc20.transfer(accounts.bob, 200), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 800);
+            assert_eq!(erc20.balance_of(accounts.bob), 195);
+            assert_eq!(erc20.balance_of(accounts.charlie), 5);
+            assert_eq!(erc20.total_supply(), 1000);
+
+            // One event for the recipient and one for the treasury.
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 3);
+            assert_transfer_event(
+                &emitted_events[1],
+                Some(AccountId::from([0x01; 32])),
+                Some(AccountId::from([0x02; 32])),
+                195,
+            );
+            assert_transfer_event(
+                &emitted_events[2],
+                Some(AccountId::from([0x01; 32])),
+                Some(AccountId::from([0x03; 32])),
+                5,
+            );
+        }
+
+        #[ink::test]
+        fn transfer_fee_split_follows_fee_bps() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut erc20 = Erc20::new_with_fee(10_000, 0, accounts.charlie);
+
+            for (fee_bps, expected_fee) in [(0, 0), (1, 0), (100, 10), (10_000, 1000)] {
+                assert_eq!(erc20.set_fee_bps(fee_bps), Ok(()));
+                let bob_before = erc20.balance_of(accounts.bob);
+                let treasury_before = erc20.balance_of(accounts.charlie);
+                assert_eq!(erc20.transfer(accounts.bob, 1000), Ok(()));
+                assert_eq!(erc20.balance_of(accounts.bob) - bob_before, 1000 - expected_fee);
+                assert_eq!(erc20.balance_of(accounts.charlie) - treasury_before, expected_fee);
+            }
+            assert_eq!(erc20.set_fee_bps(10_001), Err(Error::InvalidBasisPoints));
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.set_fee_bps(0), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "transfer fee exceeds 100%")]
+        fn new_with_fee_rejects_fee_over_100_percent() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let _erc20 = Erc20::new_with_fee(1000, 10_001, accounts.charlie);
+        }
+
+        #[ink::test]
+        fn frozen_account_cannot_send_or_receive() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut erc20 = Erc20::new(100);
+            assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+
+            assert_eq!(erc20.freeze(accounts.bob), Ok(()));
+            assert!(erc20.is_frozen(accounts.bob));
+
+            // Incoming transfers fail.
+            assert_eq!(erc20.transfer(accounts.bob, 10), Err(Error::AccountFrozen));
+            // Outgoing transfers and approvals fail.
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.alice, 10), Err(Error::AccountFrozen));
+            assert_eq!(erc20.approve(accounts.charlie, 10), Err(Error::AccountFrozen));
+            assert_eq!(erc20.increase_allowance(accounts.charlie, 10), Err(Error::AccountFrozen));
+            assert_eq!(erc20.decrease_allowance(accounts.charlie, 0), Err(Error::AccountFrozen));
+            // Only the owner may unfreeze.
+            assert_eq!(erc20.unfreeze(accounts.bob), Err(Error::NotOwner));
+
+            set_sender(accounts.alice);
+            assert_eq!(erc20.unfreeze(accounts.bob), Ok(()));
+            assert!(!erc20.is_frozen(accounts.bob));
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.alice, 10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 50);
+        }
+
+        #[ink::test]
+        fn frozen_account_cannot_be_drained_by_spender() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut erc20 = Erc20::new(100);
+            assert_eq!(erc20.approve(accounts.bob, 50), Ok(()));
+            assert_eq!(erc20.freeze(accounts.alice), Ok(()));
+
+            set_sender(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 10),
+                Err(Error::AccountFrozen)
+            );
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 50);
+        }
+
+        #[ink::test]
+        fn granted_minter_can_mint_until_revoked() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert!(erc20.has_role(DEFAULT_ADMIN_ROLE, accounts.alice));
+            assert!(erc20.has_role(MINTER_ROLE, accounts.alice));
+            assert!(!erc20.has_role(MINTER_ROLE, accounts.bob));
+
+            assert_eq!(erc20.grant_role(MINTER_ROLE, accounts.bob), Ok(()));
+            assert!(erc20.has_role(MINTER_ROLE, accounts.bob));
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.mint(accounts.charlie, 50), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 50);
+            // Minting does not make Bob an admin.
+            assert_eq!(erc20.grant_role(MINTER_ROLE, accounts.charlie), Err(Error::MissingRole));
+
+            set_sender(accounts.alice);
+            assert_eq!(erc20.revoke_role(MINTER_ROLE, accounts.bob), Ok(()));
+            assert!(!erc20.has_role(MINTER_ROLE, accounts.bob));
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.mint(accounts.charlie, 50), Err(Error::MissingRole));
+            assert_eq!(erc20.total_supply(), 150);
+        }
+
+        #[ink::test]
+        fn role_changes_emit_events() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.grant_role(MINTER_ROLE, accounts.bob), Ok(()));
+            assert_eq!(erc20.revoke_role(MINTER_ROLE, accounts.bob), Ok(()));
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 3);
+            let granted = <Event as scale::Decode>::decode(&mut &emitted_events[1].data[..])
+                .expect("encountered invalid contract event data buffer");
+            if let Event::RoleGranted(RoleGranted { role, account, sender }) = granted {
+                assert_eq!(role, MINTER_ROLE);
+                assert_eq!(account, accounts.bob);
+                assert_eq!(sender, accounts.alice);
+            } else {
+                panic!("encountered unexpected event kind: expected a RoleGranted event")
+            }
+            let revoked = <Event as scale::Decode>::decode(&mut &emitted_events[2].data[..])
+                .expect("encountered invalid contract event data buffer");
+            if let Event::RoleRevoked(RoleRevoked { role, account, sender }) = revoked {
+                assert_eq!(role, MINTER_ROLE);
+                assert_eq!(account, accounts.bob);
+                assert_eq!(sender, accounts.alice);
+            } else {
+                panic!("encountered unexpected event kind: expected a RoleRevoked event")
+            }
+        }
+
+        #[ink::test]
+        fn deposit_and_withdraw_track_native_value() {
+            let contract = set_contract_account();
+            let mut erc20 = Erc20::new(0);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            // The off-chain environment does not move the transferred value,
+            // so the contract is funded by hand.
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(100);
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract, 100);
+            assert_eq!(erc20.deposit(), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+            assert_eq!(erc20.total_supply(), 100);
+
+            let native_before =
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.alice)
+                    .expect("encountered unknown account");
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(0);
+            assert_eq!(erc20.withdraw(40), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 60);
+            assert_eq!(erc20.total_supply(), 60);
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.alice),
+                Ok(native_before + 40)
+            );
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(contract),
+                Ok(erc20.total_supply())
+            );
+
+            assert_eq!(erc20.native_deposit(accounts.alice), 60);
+            assert_eq!(erc20.wrapped_native(), 60);
+            assert_eq!(erc20.withdraw(61), Err(Error::ExceedsDeposit));
+            assert_eq!(erc20.total_supply(), 60);
+        }
+
+        #[ink::test]
+        fn undeposited_tokens_cannot_withdraw_native() {
+            let contract = set_contract_account();
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            // Native currency held for someone else, e.g. dividends.
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract, 500);
+
+            assert_eq!(erc20.withdraw(1), Err(Error::ExceedsDeposit));
+            assert_eq!(erc20.withdraw_all(), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(50);
+            assert_eq!(erc20.deposit(), Ok(()));
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(0);
+            assert_eq!(erc20.withdraw(51), Err(Error::ExceedsDeposit));
+            // Only the deposited part of the balance is paid out.
+            assert_eq!(erc20.withdraw_all(), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+            assert_eq!(erc20.native_deposit(accounts.alice), 0);
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(contract),
+                Ok(450)
+            );
+
+            // A transfer does not carry the deposit along.
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            set_sender(accounts.bob);
+            assert_eq!(erc20.withdraw(10), Err(Error::ExceedsDeposit));
+        }
+
+        #[ink::test]
+        fn expired_allowance_cannot_be_spent() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            mock::set_block_timestamp(1_000);
+
+            assert_eq!(erc20.approve_with_expiry(accounts.bob, 50, 2_000), Ok(()));
+            assert_eq!(erc20.allowance_deadline(accounts.alice, accounts.bob), Some(2_000));
+
+            // Still usable right at the deadline.
+            mock::set_block_timestamp(2_000);
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 40);
+
+            mock::set_block_timestamp(2_001);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.bob, 10),
+                Err(Error::InsufficientApproval)
+            );
+
+            // A plain approve has no deadline.
+            set_sender(accounts.alice);
+            assert_eq!(erc20.approve(accounts.bob, 30), Ok(()));
+            assert_eq!(erc20.allowance_deadline(accounts.alice, accounts.bob), None);
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 30), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 40);
+        }
+
+        #[ink::test]
+        fn increasing_an_expired_allowance_clears_the_deadline() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.approve_with_expiry(accounts.bob, 50, 2_000), Ok(()));
+            mock::set_block_timestamp(2_001);
+
+            // The expired 50 do not count; the event and state agree on 20.
+            assert_eq!(erc20.increase_allowance(accounts.bob, 20), Ok(()));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 20);
+            assert_eq!(erc20.allowance_deadline(accounts.alice, accounts.bob), None);
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let decoded = <Event as scale::Decode>::decode(&mut &emitted_events.last().unwrap().data[..])
+                .expect("encountered invalid contract event data buffer");
+            assert!(matches!(decoded, Event::Approval(Approval { value: 20, .. })));
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 20), Ok(()));
+        }
+
+        #[ink::test]
+        fn holder_count_follows_balances_to_and_from_zero() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.holder_count(), 1);
+            assert_eq!(erc20.holders_page(0, 10), vec![accounts.alice]);
+
+            assert_eq!(erc20.transfer(accounts.bob, 40), Ok(()));
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.holder_count(), 2);
+
+            // Self-transfers and zero-value transfers change nothing.
+            assert_eq!(erc20.transfer(accounts.alice, 50), Ok(()));
+            assert_eq!(erc20.transfer(accounts.charlie, 0), Ok(()));
+            assert_eq!(erc20.holder_count(), 2);
+
+            assert_eq!(erc20.mint(accounts.charlie, 5), Ok(()));
+            assert_eq!(erc20.holder_count(), 3);
+
+            // Alice empties her balance and drops out.
+            assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+            assert_eq!(erc20.holder_count(), 2);
+            set_sender(accounts.charlie);
+            assert_eq!(erc20.burn(5), Ok(()));
+            assert_eq!(erc20.holder_count(), 1);
+            assert_eq!(erc20.holders_page(0, 10), vec![accounts.bob]);
+            assert_eq!(erc20.holders_page(1, 10), Vec::<AccountId>::new());
+        }
+
+        #[ink::test]
+        fn removing_a_holder_moves_the_last_into_its_slot() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.transfer(accounts.charlie, 10), Ok(()));
+            assert_eq!(erc20.transfer(accounts.django, 10), Ok(()));
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.alice, 10), Ok(()));
+            assert_eq!(
+                erc20.holders_page(0, 10),
+                vec![accounts.alice, accounts.django, accounts.charlie]
+            );
+            assert_eq!(erc20.holders_page(1, 1), vec![accounts.django]);
+
+            // Django's moved slot is tracked, so removing him works as well.
+            set_sender(accounts.django);
+            assert_eq!(erc20.transfer(accounts.alice, 10), Ok(()));
+            assert_eq!(erc20.holders_page(0, 10), vec![accounts.alice, accounts.charlie]);
+            assert_eq!(erc20.holder_count(), 2);
+        }
+
+        #[ink::test]
+        fn holder_count_includes_treasury_once() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut erc20 = Erc20::new_with_fee(1000, 100, accounts.charlie);
+
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+            assert_eq!(erc20.holder_count(), 3);
+            let mut holders = erc20.holders_page(0, 10);
+            holders.sort();
+            assert_eq!(holders, vec![accounts.alice, accounts.bob, accounts.charlie]);
+        }
+
+        #[ink::test]
+        fn mint_and_burn_events_are_emitted_alongside_transfers() {
+            let mut erc20 = Erc20::new_with_mint_burn_events(100, false);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.mint(accounts.bob, 12), Ok(()));
+            assert_eq!(erc20.burn(30), Ok(()));
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 5);
+            assert_transfer_event(&emitted_events[1], None, Some(accounts.bob), 12);
+            let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[2].data[..])
+                .expect("encountered invalid contract event data buffer");
+            if let Event::Mint(Mint { to, value }) = decoded {
+                assert_eq!(to, accounts.bob);
+                assert_eq!(value, 12);
+            } else {
+                panic!("encountered unexpected event kind: expected a Mint event")
+            }
+            assert_transfer_event(&emitted_events[3], Some(accounts.alice), None, 30);
+            let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[4].data[..])
+                .expect("encountered invalid contract event data buffer");
+            if let Event::Burn(Burn { from, value }) = decoded {
+                assert_eq!(from, accounts.alice);
+                assert_eq!(value, 30);
+            } else {
+                panic!("encountered unexpected event kind: expected a Burn event")
+            }
+        }
+
+        #[ink::test]
+        fn mint_and_burn_events_can_replace_transfers() {
+            let mut erc20 = Erc20::new_with_mint_burn_events(100, true);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.mint(accounts.bob, 12), Ok(()));
+            assert_eq!(erc20.burn(30), Ok(()));
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 3);
+            let minted = <Event as scale::Decode>::decode(&mut &emitted_events[1].data[..])
+                .expect("encountered invalid contract event data buffer");
+            assert!(matches!(minted, Event::Mint(Mint { value: 12, .. })));
+            let burned = <Event as scale::Decode>::decode(&mut &emitted_events[2].data[..])
+                .expect("encountered invalid contract event data buffer");
+            assert!(matches!(burned, Event::Burn(Burn { value: 30, .. })));
+            assert_topics(&emitted_events[1], vec![
+                encoded_into_hash(&PrefixedValue {
+                    value: b"Erc20::Mint",
+                    prefix: b"",
+                }),
+                encoded_into_hash(&PrefixedValue {
+                    prefix: b"Erc20::Mint::to",
+                    value: &accounts.bob,
+                }),
+            ]);
+        }
+
+        #[ink::test]
+        fn recover_tokens_calls_foreign_token() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            mock::set_token_response(accounts.django, true);
+            mock::set_token_response(accounts.eve, false);
+
+            assert_eq!(erc20.recover_tokens(accounts.django, accounts.bob, 10), Ok(()));
+            assert_eq!(
+                erc20.recover_tokens(accounts.eve, accounts.bob, 10),
+                Err(Error::RecoveryFailed)
+            );
+            assert_eq!(
+                erc20.recover_tokens(accounts.frank, accounts.bob, 10),
+                Err(Error::RecoveryFailed)
+            );
+
+            set_sender(accounts.bob);
+            assert_eq!(
+                erc20.recover_tokens(accounts.django, accounts.bob, 10),
+                Err(Error::NotOwner)
+            );
+        }
+
+        #[ink::test]
+        fn recover_tokens_rejects_own_token() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let contract = ink_env::test::callee::<ink_env::DefaultEnvironment>();
+            mock::set_token_response(contract, true);
+
+            assert_eq!(
+                erc20.recover_tokens(contract, accounts.bob, 10),
+                Err(Error::CannotRecoverSelf)
+            );
+        }
+
+        #[ink::test]
+        fn safe_approve_checks_current_allowance() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.safe_approve(accounts.bob, 0, 50), Ok(()));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 50);
+
+            // Bob spends part of the allowance before Alice lowers it.
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 20), Ok(()));
+
+            set_sender(accounts.alice);
+            assert_eq!(erc20.safe_approve(accounts.bob, 50, 10), Err(Error::AllowanceChanged));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 30);
+            assert_eq!(erc20.safe_approve(accounts.bob, 30, 10), Ok(()));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 10);
+
+            // Instantiation, two approvals and Bob's transfer with its
+            // Transfer, TransferFrom and Approval events.
+            assert_eq!(ink_env::test::recorded_events().count(), 6);
+        }
+
+        #[ink::test]
+        fn new_distributed_credits_every_allocation() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let erc20 = Erc20::new_distributed(vec![
+                (accounts.bob, 100),
+                (accounts.charlie, 200),
+                (accounts.django, 300),
+            ]);
+
+            assert_eq!(erc20.balance_of(accounts.alice), 0);
+            assert_eq!(erc20.balance_of(accounts.bob), 100);
+            assert_eq!(erc20.balance_of(accounts.charlie), 200);
+            assert_eq!(erc20.balance_of(accounts.django), 300);
+            assert_eq!(erc20.total_supply(), 600);
+            assert_eq!(erc20.holder_count(), 3);
+            assert_eq!(erc20.owner(), accounts.alice);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 3);
+            assert_transfer_event(&emitted_events[0], None, Some(accounts.bob), 100);
+            assert_transfer_event(&emitted_events[1], None, Some(accounts.charlie), 200);
+            assert_transfer_event(&emitted_events[2], None, Some(accounts.django), 300);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "no allocations given")]
+        fn new_distributed_rejects_empty_allocations() {
+            let _erc20 = Erc20::new_distributed(Vec::new());
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "duplicate allocation")]
+        fn new_distributed_rejects_duplicate_accounts() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let _erc20 = Erc20::new_distributed(vec![(accounts.bob, 1), (accounts.bob, 2)]);
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "initial supply overflows")]
+        fn new_distributed_rejects_overflowing_supply() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let _erc20 = Erc20::new_distributed(vec![(accounts.bob, Balance::MAX), (accounts.charlie, 1)]);
+        }
+
+        #[ink::test]
+        fn force_transfer_moves_frozen_funds() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.transfer(accounts.bob, 60), Ok(()));
+            assert_eq!(erc20.freeze(accounts.bob), Ok(()));
+
+            assert_eq!(erc20.force_transfer(accounts.bob, accounts.charlie, 50), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 10);
+            assert_eq!(erc20.balance_of(accounts.charlie), 50);
+            assert_eq!(
+                erc20.force_transfer(accounts.bob, accounts.charlie, 11),
+                Err(Error::InsufficientBalance)
+            );
+            // Moving tokens to the same account still needs them to exist.
+            assert_eq!(
+                erc20.force_transfer(accounts.bob, accounts.bob, 11),
+                Err(Error::InsufficientBalance)
+            );
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 5);
+            assert_transfer_event(&emitted_events[3], Some(accounts.bob), Some(accounts.charlie), 50);
+            let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[4].data[..])
+                .expect("encountered invalid contract event data buffer");
+            if let Event::ForcedTransfer(ForcedTransfer { by, from, to, value }) = decoded {
+                assert_eq!(by, accounts.alice);
+                assert_eq!(from, accounts.bob);
+                assert_eq!(to, accounts.charlie);
+                assert_eq!(value, 50);
+            } else {
+                panic!("encountered unexpected event kind: expected a ForcedTransfer event")
+            }
+        }
+
+        #[ink::test]
+        fn non_owner_cannot_force_transfer() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            set_sender(accounts.bob);
+            assert_eq!(
+                erc20.force_transfer(accounts.alice, accounts.bob, 50),
+                Err(Error::NotOwner)
+            );
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+        }
+
+        #[ink::test]
+        fn large_transfers_wait_for_timelock() {
+            let mut erc20 = Erc20::new(1000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.set_timelock(Some(100), 500), Ok(()));
+            mock::set_block_timestamp(1_000);
+
+            // Small transfers go through right away.
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+            assert_eq!(erc20.transfer(accounts.bob, 101), Err(Error::TimelockRequired));
+
+            assert_eq!(erc20.queue_transfer(accounts.bob, 400), Ok(0));
+            assert_eq!(
+                erc20.queued_transfer(0),
+                Some(QueuedTransfer {
+                    from: accounts.alice,
+                    to: accounts.bob,
+                    value: 400,
+                    ready_at: 1_500,
+                })
+            );
+
+            mock::set_block_timestamp(1_499);
+            assert_eq!(erc20.execute_queued(0), Err(Error::TimelockNotElapsed));
+            assert_eq!(erc20.balance_of(accounts.bob), 100);
+
+            mock::set_block_timestamp(1_500);
+            set_sender(accounts.charlie);
+            assert_eq!(erc20.execute_queued(0), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 500);
+            assert_eq!(erc20.balance_of(accounts.alice), 500);
+
+            // A queued transfer runs only once.
+            assert_eq!(erc20.execute_queued(0), Err(Error::UnknownQueuedTransfer));
+            assert_eq!(erc20.queued_transfer(0), None);
+        }
+
+        #[ink::test]
+        fn set_timelock_is_owner_only() {
+            let mut erc20 = Erc20::new(1000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.set_timelock(Some(1), 0), Err(Error::NotOwner));
+            assert_eq!(erc20.large_transfer_threshold(), None);
+        }
+
+        #[ink::test]
+        fn vesting_releases_linearly() {
+            let contract = set_contract_account();
+            let mut erc20 = Erc20::new(1000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.create_vesting(accounts.bob, 400, 1_000, 4_000), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 600);
+            assert_eq!(erc20.balance_of(contract), 400);
+            assert_eq!(
+                erc20.create_vesting(accounts.bob, 1, 0, 0),
+                Err(Error::VestingExists)
+            );
+
+            // Nothing is releasable before the start.
+            mock::set_block_timestamp(999);
+            assert_eq!(erc20.releasable(accounts.bob), 0);
+            set_sender(accounts.bob);
+            assert_eq!(erc20.release(), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+
+            // A quarter through the schedule.
+            mock::set_block_timestamp(2_000);
+            assert_eq!(erc20.releasable(accounts.bob), 100);
+            assert_eq!(erc20.release(), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 100);
+            assert_eq!(erc20.releasable(accounts.bob), 0);
+
+            // Everything after the end, minus what was already released.
+            mock::set_block_timestamp(10_000);
+            assert_eq!(erc20.releasable(accounts.bob), 300);
+            assert_eq!(erc20.release(), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 400);
+            assert_eq!(erc20.balance_of(contract), 0);
+            assert_eq!(erc20.vesting_of(accounts.bob).map(|vesting| vesting.released), Some(400));
+        }
+
+        #[ink::test]
+        fn release_respects_pause_and_freeze() {
+            let mut erc20 = Erc20::new(1000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.create_vesting(accounts.bob, 400, 0, 4_000), Ok(()));
+            mock::set_block_timestamp(1_000);
+
+            assert_eq!(erc20.pause(), Ok(()));
+            set_sender(accounts.bob);
+            assert_eq!(erc20.release(), Err(Error::Paused));
+            set_sender(accounts.alice);
+            assert_eq!(erc20.unpause(), Ok(()));
+
+            assert_eq!(erc20.freeze(accounts.bob), Ok(()));
+            set_sender(accounts.bob);
+            assert_eq!(erc20.release(), Err(Error::AccountFrozen));
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.releasable(accounts.bob), 100);
+
+            set_sender(accounts.alice);
+            assert_eq!(erc20.unfreeze(accounts.bob), Ok(()));
+            set_sender(accounts.bob);
+            assert_eq!(erc20.release(), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 100);
+        }
+
+        #[ink::test]
+        fn non_owner_cannot_create_vesting() {
+            let mut erc20 = Erc20::new(1000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            set_sender(accounts.bob);
+            assert_eq!(
+                erc20.create_vesting(accounts.bob, 400, 0, 1),
+                Err(Error::NotOwner)
+            );
+        }
+
+        #[ink::test]
+        fn staking_rewards_accrue_per_block() {
+            set_contract_account();
+            let mut erc20 = Erc20::new(1000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.set_reward_rate(2 * REWARD_PRECISION), Ok(()));
+
+            assert_eq!(erc20.stake(100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 900);
+            assert_eq!(erc20.staked_of(accounts.alice), 100);
+            for _ in 0..3 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            assert_eq!(erc20.pending_rewards(accounts.alice), 600);
+
+            // Adding stake keeps the rewards earned so far.
+            assert_eq!(erc20.stake(50), Ok(()));
+            assert_eq!(erc20.pending_rewards(accounts.alice), 600);
+            for _ in 0..2 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            assert_eq!(erc20.pending_rewards(accounts.alice), 600 + 150 * 2 * 2);
+
+            assert_eq!(erc20.claim_rewards(), Ok(()));
+            assert_eq!(erc20.pending_rewards(accounts.alice), 0);
+            assert_eq!(erc20.balance_of(accounts.alice), 850 + 1200);
+            assert_eq!(erc20.total_supply(), 2200);
+
+            assert_eq!(erc20.unstake(151), Err(Error::InsufficientStake));
+            assert_eq!(erc20.unstake(150), Ok(()));
+            assert_eq!(erc20.staked_of(accounts.alice), 0);
+            assert_eq!(erc20.balance_of(accounts.alice), 2200);
+        }
+
+        #[ink::test]
+        fn reward_rate_changes_only_apply_from_then_on() {
+            set_contract_account();
+            let mut erc20 = Erc20::new(1000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            // A tenth of a token per staked token and block.
+            assert_eq!(erc20.set_reward_rate(REWARD_PRECISION / 10), Ok(()));
+            assert_eq!(erc20.stake(100), Ok(()));
+            for _ in 0..4 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            assert_eq!(erc20.pending_rewards(accounts.alice), 40);
+
+            // Raising the rate does not reach back to the first four blocks.
+            assert_eq!(erc20.set_reward_rate(REWARD_PRECISION), Ok(()));
+            assert_eq!(erc20.pending_rewards(accounts.alice), 40);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.pending_rewards(accounts.alice), 140);
+
+            assert_eq!(erc20.claim_rewards(), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 900 + 140);
+        }
+
+        #[ink::test]
+        fn staking_emits_events() {
+            let mut erc20 = Erc20::new(1000);
+            assert_eq!(erc20.set_reward_rate(REWARD_PRECISION), Ok(()));
+            assert_eq!(erc20.stake(10), Ok(()));
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.claim_rewards(), Ok(()));
+            assert_eq!(erc20.unstake(10), Ok(()));
+
+            let emitted_events = ink_env::test::recorded_events()
+                .map(|event| {
+                    <Event as scale::Decode>::decode(&mut &event.data[..])
+                        .expect("encountered invalid contract event data buffer")
+                })
+                .collect::<Vec<_>>();
+            assert!(matches!(emitted_events[2], Event::Staked(Staked { amount: 10, .. })));
+            assert!(matches!(emitted_events[4], Event::RewardPaid(RewardPaid { reward: 10, .. })));
+            assert!(matches!(emitted_events[6], Event::Unstaked(Unstaked { amount: 10, .. })));
+        }
+
+        #[ink::test]
+        fn reentrant_transfer_and_call_should_fail() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            mock::set_reentrant_receiver(accounts.django);
+
+            assert_eq!(erc20.transfer_and_call(accounts.django, 30, Vec::new()), Ok(()));
+            assert_eq!(mock::take_reentry(), Some(Err(Error::ReentrantCall)));
+            // Only the outer transfer went through.
+            assert_eq!(erc20.balance_of(accounts.django), 30);
+            assert_eq!(erc20.balance_of(accounts.alice), 70);
+
+            // The guard is cleared again afterwards.
+            set_sender(accounts.alice);
+            assert_eq!(erc20.transfer_and_call(accounts.bob, 10, Vec::new()), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 10);
+        }
+
+        #[ink::test]
+        fn transfer_from_and_burn_from_spend_allowance_alike() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.approve(accounts.bob, 50), Ok(()));
+            assert_eq!(erc20.approve(accounts.charlie, 50), Ok(()));
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 20), Ok(()));
+            set_sender(accounts.charlie);
+            assert_eq!(erc20.burn_from(accounts.alice, 20), Ok(()));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 30);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.charlie), 30);
+
+            // Both report the remaining allowance last.
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let transfer_from_approval = decode_approval(&emitted_events[5]);
+            let burn_from_approval = decode_approval(&emitted_events[7]);
+            assert_eq!(transfer_from_approval.value, burn_from_approval.value);
+
+            // An infinite allowance is left alone by both.
+            set_sender(accounts.alice);
+            assert_eq!(erc20.approve(accounts.bob, Balance::MAX), Ok(()));
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.burn_from(accounts.alice, 10), Ok(()));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), Balance::MAX);
+        }
+
+        #[ink::test]
+        fn before_token_transfer_checks_present_sides() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+            assert_eq!(erc20.freeze(accounts.bob), Ok(()));
+
+            // Bob is checked as recipient of transfers and mints ...
+            assert_eq!(erc20.transfer(accounts.bob, 1), Err(Error::AccountFrozen));
+            assert_eq!(erc20.mint(accounts.bob, 1), Err(Error::AccountFrozen));
+            // ... and as sender of transfers and burns.
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.alice, 1), Err(Error::AccountFrozen));
+            assert_eq!(erc20.burn(1), Err(Error::AccountFrozen));
+
+            // Mints and burns without a frozen side go through.
+            set_sender(accounts.alice);
+            assert_eq!(erc20.mint(accounts.charlie, 1), Ok(()));
+            assert_eq!(erc20.burn(1), Ok(()));
+
+            // Pausing blocks all three.
+            assert_eq!(erc20.pause(), Ok(()));
+            assert_eq!(erc20.transfer(accounts.charlie, 1), Err(Error::Paused));
+            assert_eq!(erc20.mint(accounts.charlie, 1), Err(Error::Paused));
+            assert_eq!(erc20.burn(1), Err(Error::Paused));
+        }
+
+        #[ink::test]
+        fn batch_approve_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(
+                erc20.batch_approve(vec![
+                    (accounts.bob, 10),
+                    (accounts.charlie, 20),
+                    (accounts.django, 30),
+                ]),
+                Ok(())
+            );
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 10);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.charlie), 20);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.django), 30);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 4);
+            let approval = decode_approval(&emitted_events[3]);
+            assert_eq!(approval.spender, accounts.django);
+            assert_eq!(approval.value, 30);
+        }
+
+        #[ink::test]
+        fn batch_approve_checks_length_and_pause() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            let approvals = vec![(accounts.bob, 1); MAX_BATCH_LEN + 1];
+            assert_eq!(erc20.batch_approve(approvals), Err(Error::BatchTooLarge));
+
+            assert_eq!(erc20.pause(), Ok(()));
+            assert_eq!(erc20.batch_approve(vec![(accounts.bob, 1)]), Err(Error::Paused));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn transfer_returning_reports_remaining_balance() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.transfer_returning(accounts.bob, 30), Ok(70));
+            assert_eq!(erc20.balance_of(accounts.alice), 70);
+            assert_eq!(erc20.transfer_returning(accounts.bob, 71), Err(Error::InsufficientBalance));
+
+            // The fee is taken out of the transferred value, not on top of it.
+            assert_eq!(erc20.set_treasury(accounts.charlie), Ok(()));
+            assert_eq!(erc20.set_fee_bps(1_000), Ok(()));
+            assert_eq!(erc20.transfer_returning(accounts.bob, 20), Ok(50));
+            assert_eq!(erc20.balance_of(accounts.alice), 50);
+        }
+
+        #[ink::test]
+        fn mint_and_burn_totals_track_supply() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.total_minted(), 100);
+            assert_eq!(erc20.total_burned(), 0);
+            assert_supply_accounting(&erc20);
+
+            assert_eq!(erc20.mint(accounts.bob, 50), Ok(()));
+            assert_supply_accounting(&erc20);
+            assert_eq!(erc20.burn(30), Ok(()));
+            assert_supply_accounting(&erc20);
+            assert_eq!(erc20.burn_from_any(accounts.bob, 20), Ok(()));
+            assert_supply_accounting(&erc20);
+            // Transfers leave both totals alone.
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            assert_supply_accounting(&erc20);
+
+            assert_eq!(erc20.total_minted(), 150);
+            assert_eq!(erc20.total_burned(), 50);
+            assert_eq!(erc20.total_supply(), 100);
+        }
+
+        #[ink::test]
+        fn sweep_dust_moves_only_small_balances() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut erc20 = Erc20::new_with_fee(1000, 0, accounts.eve);
+            assert_eq!(erc20.transfer(accounts.bob, 3), Ok(()));
+            assert_eq!(erc20.transfer(accounts.charlie, 10), Ok(()));
+            assert_eq!(erc20.transfer(accounts.django, 9), Ok(()));
+
+            assert_eq!(
+                erc20.sweep_dust(vec![accounts.bob, accounts.charlie, accounts.django, accounts.frank], 10),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.balance_of(accounts.charlie), 10);
+            assert_eq!(erc20.balance_of(accounts.django), 0);
+            assert_eq!(erc20.balance_of(accounts.eve), 12);
+            assert_eq!(erc20.total_supply(), 1000);
+
+            // Two dust transfers on top of instantiation and the three transfers.
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 6);
+            assert_transfer_event(&emitted_events[4], Some(accounts.bob), Some(accounts.eve), 3);
+            assert_transfer_event(&emitted_events[5], Some(accounts.django), Some(accounts.eve), 9);
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.sweep_dust(vec![accounts.charlie], 100), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn fees_and_sweeps_need_a_treasury() {
+            let mut erc20 = Erc20::new(1000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.treasury(), None);
+            assert_eq!(erc20.set_fee_bps(100), Err(Error::NoTreasury));
+            assert_eq!(erc20.set_fee_bps(0), Ok(()));
+            assert_eq!(erc20.transfer(accounts.bob, 3), Ok(()));
+            assert_eq!(erc20.sweep_dust(vec![accounts.bob], 10), Err(Error::NoTreasury));
+            assert_eq!(erc20.balance_of(accounts.bob), 3);
+
+            assert_eq!(
+                erc20.set_treasury(AccountId::from([0u8; 32])),
+                Err(Error::ZeroAddress)
+            );
+            assert_eq!(erc20.set_treasury(accounts.eve), Ok(()));
+            assert_eq!(erc20.set_fee_bps(100), Ok(()));
+            assert_eq!(erc20.sweep_dust(vec![accounts.bob], 10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.eve), 3);
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.set_treasury(accounts.bob), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn circulating_supply_leaves_out_excluded_accounts() {
+            let mut erc20 = Erc20::new(1000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.transfer(accounts.eve, 300), Ok(()));
+            assert_eq!(erc20.circulating_supply(), 1000);
+
+            assert_eq!(erc20.add_excluded(accounts.eve), Ok(()));
+            // Adding an account twice counts it once.
+            assert_eq!(erc20.add_excluded(accounts.eve), Ok(()));
+            assert_eq!(erc20.circulating_supply(), 700);
+            assert_eq!(erc20.excluded_accounts(), vec![accounts.eve]);
+
+            assert_eq!(erc20.remove_excluded(accounts.eve), Ok(()));
+            assert_eq!(erc20.circulating_supply(), 1000);
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.add_excluded(accounts.bob), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn domain_separator_depends_on_account_id() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(accounts.django);
+            let first = Erc20::new(100);
+            let first_separator = first.domain_separator();
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(accounts.eve);
+            let second = Erc20::new(100);
+
+            assert_ne!(first_separator, second.domain_separator());
+            // The permit message commits to the separator as well.
+            let second_hash = second.permit_hash(accounts.alice, accounts.bob, 1, 0, 0);
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(accounts.django);
+            assert_eq!(first.domain_separator(), first_separator);
+            assert_ne!(first.permit_hash(accounts.alice, accounts.bob, 1, 0, 0), second_hash);
+        }
+
+        #[ink::test]
+        fn base_unit_conversion_with_18_decimals() {
+            let erc20 = Erc20::new(0);
+            let half = 500_000_000_000_000_000;
+
+            assert_eq!(erc20.to_base_units(1, half), 1_500_000_000_000_000_000);
+            assert_eq!(erc20.to_base_units(3, 0), 3_000_000_000_000_000_000);
+            assert_eq!(erc20.to_base_units(0, 1), 1);
+            assert_eq!(erc20.from_base_units(1_500_000_000_000_000_000), (1, half));
+            assert_eq!(erc20.from_base_units(3_000_000_000_000_000_000), (3, 0));
+            assert_eq!(erc20.from_base_units(1), (0, 1));
+        }
+
+        #[ink::test]
+        fn base_unit_conversion_with_6_decimals() {
+            let erc20 = Erc20::new_with_metadata(0, None, None, 6);
+
+            // 1.05 tokens.
+            assert_eq!(erc20.to_base_units(1, 50_000_000_000_000_000), 1_050_000);
+            assert_eq!(erc20.to_base_units(7, 0), 7_000_000);
+            // Digits past the sixth decimal are dropped.
+            assert_eq!(erc20.to_base_units(0, 123_456_789_000_000_000), 123_456);
+            assert_eq!(erc20.to_base_units(0, 999_999_999_999), 0);
+
+            assert_eq!(erc20.from_base_units(1_050_000), (1, 50_000_000_000_000_000));
+            assert_eq!(erc20.from_base_units(7_000_000), (7, 0));
+            assert_eq!(erc20.from_base_units(123_456), (0, 123_456_000_000_000_000));
+        }
+
+        #[ink::test]
+        fn base_unit_conversion_with_max_decimals() {
+            let erc20 = Erc20::new_with_metadata(0, None, None, MAX_DECIMALS);
+            let unit = 100_000_000_000_000_000_000_000_000_000_000_000_000;
+
+            assert_eq!(erc20.to_base_units(1, 0), unit);
+            assert_eq!(erc20.from_base_units(unit), (1, 0));
+            assert_eq!(
+                erc20.from_base_units(2 * unit + unit / 2),
+                (2, 500_000_000_000_000_000)
+            );
+        }
+
+        #[ink::test]
+        #[should_panic(expected = "too many decimals")]
+        fn new_with_metadata_rejects_too_many_decimals() {
+            let _erc20 = Erc20::new_with_metadata(0, None, None, MAX_DECIMALS + 1);
+        }
+
+        #[ink::test]
+        fn non_owner_cannot_upgrade() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            // The off-chain environment cannot swap code, so this only passes
+            // if the owner check comes first.
+            set_sender(accounts.bob);
+            assert_eq!(erc20.upgrade([0x42; 32]), Err(Error::NotOwner));
+            assert_eq!(ink_env::test::recorded_events().count(), 1);
+        }
+
+        #[ink::test]
+        fn mint_batch_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(
+                erc20.mint_batch(vec![(accounts.bob, 10), (accounts.charlie, 20)]),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 10);
+            assert_eq!(erc20.balance_of(accounts.charlie), 20);
+            assert_eq!(erc20.total_supply(), 130);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 3);
+            assert_transfer_event(&emitted_events[1], None, Some(accounts.bob), 10);
+            assert_transfer_event(&emitted_events[2], None, Some(accounts.charlie), 20);
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.mint_batch(vec![(accounts.bob, 1)]), Err(Error::MissingRole));
+        }
+
+        #[ink::test]
+        fn mint_batch_over_cap_should_fail() {
+            let mut erc20 = Erc20::new_capped(100, 150);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            // Each entry fits on its own, but not all of them together.
+            assert_eq!(
+                erc20.mint_batch(vec![(accounts.bob, 30), (accounts.charlie, 30)]),
+                Err(Error::CapExceeded)
+            );
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.total_supply(), 100);
+        }
+
+        #[ink::test]
+        fn mint_batch_too_large_should_fail() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            let recipients = vec![(accounts.bob, 1); MAX_BATCH_LEN + 1];
+            assert_eq!(erc20.mint_batch(recipients), Err(Error::BatchTooLarge));
+            assert_eq!(erc20.total_supply(), 100);
+        }
+
+        #[ink::test]
+        fn emission_mints_per_elapsed_block() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.set_emission_recipient(accounts.eve), Ok(()));
+            assert_eq!(erc20.set_emission_per_block(5), Ok(()));
+
+            for _ in 0..3 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            set_sender(accounts.bob);
+            assert_eq!(erc20.mint_emission(), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.eve), 15);
+            assert_eq!(erc20.total_supply(), 115);
+
+            // A second call within the same block mints nothing.
+            assert_eq!(erc20.mint_emission(), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.eve), 15);
+
+            // Rate changes pay out at the old rate first.
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            set_sender(accounts.alice);
+            assert_eq!(erc20.set_emission_per_block(1), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.eve), 20);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.mint_emission(), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.eve), 21);
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.set_emission_per_block(100), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn emission_setters_work_while_paused_and_at_cap() {
+            let mut erc20 = Erc20::new_capped(100, 110);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.set_emission_recipient(accounts.eve), Ok(()));
+            assert_eq!(erc20.set_emission_per_block(5), Ok(()));
+
+            // While paused the pending emission cannot be minted and is dropped.
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.pause(), Ok(()));
+            assert_eq!(erc20.set_emission_per_block(4), Ok(()));
+            assert_eq!(erc20.set_emission_recipient(accounts.frank), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.eve), 0);
+            assert_eq!(erc20.unpause(), Ok(()));
+
+            // Three blocks at 4 exceed the cap, so the owner can still stop it.
+            for _ in 0..3 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            assert_eq!(erc20.mint_emission(), Err(Error::CapExceeded));
+            assert_eq!(erc20.set_emission_per_block(0), Ok(()));
+            assert_eq!(erc20.emission_per_block(), 0);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.mint_emission(), Ok(()));
+            assert_eq!(erc20.total_supply(), 100);
+        }
+
+        #[ink::test]
+        fn account_state_matches_getters() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.transfer(accounts.bob, 40), Ok(()));
+            assert_eq!(erc20.approve(accounts.charlie, 25), Ok(()));
+
+            assert_eq!(erc20.account_state(accounts.alice, accounts.charlie), (60, 25));
+            assert_eq!(
+                erc20.account_state(accounts.alice, accounts.charlie),
+                (
+                    erc20.balance_of(accounts.alice),
+                    erc20.allowance(accounts.alice, accounts.charlie)
+                )
+            );
+            assert_eq!(erc20.account_state(accounts.bob, accounts.charlie), (40, 0));
+        }
+
+        #[ink::test]
+        fn max_tx_amount_limits_non_exempt_senders() {
+            let mut erc20 = Erc20::new(1000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.set_max_tx_amount(Some(100)), Ok(()));
+            assert_eq!(erc20.max_tx_amount(), Some(100));
+
+            // The owner is exempt.
+            assert_eq!(erc20.transfer(accounts.bob, 500), Ok(()));
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 101), Err(Error::MaxTxExceeded));
+            assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+            // Sending to the owner is exempt as well.
+            assert_eq!(erc20.transfer(accounts.alice, 200), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 200);
+
+            assert_eq!(erc20.set_max_tx_amount(None), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn transfer_cooldown_delays_second_transfer() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.set_transfer_cooldown(60_000), Ok(()));
+            mock::set_block_timestamp(1_000);
+
+            // The owner is exempt.
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 5), Ok(()));
+            assert_eq!(erc20.transfer(accounts.charlie, 5), Err(Error::CooldownActive));
+
+            mock::set_block_timestamp(60_999);
+            assert_eq!(erc20.transfer(accounts.charlie, 5), Err(Error::CooldownActive));
+            mock::set_block_timestamp(61_000);
+            assert_eq!(erc20.transfer(accounts.charlie, 5), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 10);
+        }
+
+        /// Describes a token knowing nothing about `Erc20` beyond its metadata
+        /// interface.
+        ///
+        /// ink! trait definitions are not object safe, so a generic stands in
+        /// for a `&dyn PSP22Metadata`, with the message outputs pinned down.
+        fn describe<T>(token: &T) -> (Option<String>, Option<String>, u8)
+        where
+            T: PSP22Metadata<
+                tokenNameOutput = Option<String>,
+                tokenSymbolOutput = Option<String>,
+                tokenDecimalsOutput = u8,
+            >,
+        {
+            (token.token_name(), token.token_symbol(), token.token_decimals())
+        }
+
+        #[ink::test]
+        fn psp22_metadata_interface_works() {
+            let erc20 = Erc20::new_with_metadata(
+                100,
+                Some(String::from("Ink Token")),
+                Some(String::from("INK")),
+                12,
+            );
+
+            assert_eq!(
+                describe(&erc20),
+                (Some(String::from("Ink Token")), Some(String::from("INK")), 12)
+            );
+            assert_eq!(describe(&Erc20::new(100)), (None, None, 18));
+        }
+
+        #[ink::test]
+        fn export_state_lists_balances_and_allowances() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.transfer(accounts.bob, 30), Ok(()));
+            assert_eq!(erc20.transfer(accounts.charlie, 20), Ok(()));
+            assert_eq!(erc20.approve(accounts.bob, 15), Ok(()));
+            assert_eq!(erc20.approve(accounts.django, 5), Ok(()));
+            // Fully spent and revoked allowances drop out.
+            assert_eq!(erc20.approve(accounts.django, 0), Ok(()));
+            set_sender(accounts.bob);
+            assert_eq!(erc20.approve(accounts.alice, 10), Ok(()));
+            assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 15), Ok(()));
+            assert_eq!(erc20.approve(accounts.charlie, 7), Ok(()));
+            assert_eq!(erc20.revoke_allowance(accounts.charlie), Ok(()));
+
+            let (total_supply, mut balances, mut allowances) =
+                erc20.export_state().expect("encountered too many entries");
+            balances.sort();
+            allowances.sort();
+            assert_eq!(total_supply, 100);
+            assert_eq!(
+                balances,
+                vec![(accounts.alice, 35), (accounts.bob, 45), (accounts.charlie, 20)]
+            );
+            assert_eq!(allowances, vec![((accounts.bob, accounts.alice), 10)]);
+        }
+
+        #[ink::test]
+        fn transfer_burn_shrinks_supply() {
+            let mut erc20 = Erc20::new(10_000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            // 1% is burned.
+            assert_eq!(erc20.set_burn_bps(100), Ok(()));
+
+            assert_eq!(erc20.transfer(accounts.bob, 1000), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 990);
+            assert_eq!(erc20.balance_of(accounts.alice), 9000);
+            assert_eq!(erc20.total_supply(), 9990);
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 500), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 495);
+            assert_eq!(erc20.total_supply(), 9985);
+            assert_eq!(erc20.total_burned(), 15);
+
+            // The burn is reported as a transfer to nobody.
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_transfer_event(emitted_events.last().unwrap(), Some(accounts.bob), None, 5);
+        }
+
+        #[ink::test]
+        fn transfer_burn_composes_with_fee() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut erc20 = Erc20::new_with_fee(10_000, 200, accounts.eve);
+            assert_eq!(erc20.set_burn_bps(300), Ok(()));
+
+            assert_eq!(erc20.transfer(accounts.bob, 1000), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 950);
+            assert_eq!(erc20.balance_of(accounts.eve), 20);
+            assert_eq!(erc20.balance_of(accounts.alice), 9000);
+            assert_eq!(erc20.total_supply(), 9970);
+
+            // Fee and burn together cannot exceed the transferred value.
+            assert_eq!(erc20.set_burn_bps(9_801), Err(Error::InvalidBasisPoints));
+            assert_eq!(erc20.set_fee_bps(9_701), Err(Error::InvalidBasisPoints));
+            assert_eq!(erc20.set_burn_bps(9_800), Ok(()));
+        }
+
+        #[ink::test]
+        fn fund_adds_to_native_balance() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let contract = ink_env::test::callee::<ink_env::DefaultEnvironment>();
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract, 0);
+            assert_eq!(erc20.native_balance(), 0);
+
+            // The off-chain environment does not move the transferred value,
+            // so the contract is funded by hand.
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(250);
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract, 250);
+            erc20.fund();
+            assert_eq!(erc20.native_balance(), 250);
+            assert_eq!(erc20.total_supply(), 100);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[1].data[..])
+                .expect("encountered invalid contract event data buffer");
+            if let Event::Funded(Funded { from, amount }) = decoded {
+                assert_eq!(from, accounts.alice);
+                assert_eq!(amount, 250);
+            } else {
+                panic!("encountered unexpected event kind: expected a Funded event")
+            }
+        }
+
+        #[ink::test]
+        fn revoke_allowance_removes_entry() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.approve(accounts.bob, 40), Ok(()));
+            assert_eq!(erc20.pause(), Ok(()));
+
+            assert_eq!(erc20.revoke_allowance(accounts.bob), Ok(()));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(erc20.allowances.get((accounts.alice, accounts.bob)), None);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let approval = decode_approval(emitted_events.last().unwrap());
+            assert_eq!(approval.spender, accounts.bob);
+            assert_eq!(approval.value, 0);
+        }
+
+        #[ink::test]
+        fn multisig_executes_after_second_confirmation() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(
+                erc20.set_multisig(vec![accounts.bob, accounts.charlie, accounts.django], 2),
+                Ok(())
+            );
+
+            // The owner can no longer mint, singly or in a batch, or pause directly.
+            assert_eq!(erc20.mint(accounts.eve, 50), Err(Error::MultisigRequired));
+            assert_eq!(
+                erc20.mint_batch(vec![(accounts.eve, 50)]),
+                Err(Error::MultisigRequired)
+            );
+            assert_eq!(erc20.total_supply(), 100);
+            assert_eq!(erc20.pause(), Err(Error::MultisigRequired));
+            assert_eq!(erc20.propose_action(AdminAction::Pause), Err(Error::NotAdmin));
+
+            set_sender(accounts.bob);
+            let mint = AdminAction::Mint { to: accounts.eve, value: 50 };
+            let action_hash = erc20.propose_action(mint.clone()).expect("encountered failed proposal");
+            assert_eq!(action_hash, erc20.action_hash(mint.clone()));
+            assert_eq!(erc20.propose_action(mint), Err(Error::ActionAlreadyProposed));
+            assert_eq!(erc20.confirm_action(action_hash), Err(Error::AlreadyConfirmed));
+            // One confirmation is not enough.
+            assert_eq!(erc20.balance_of(accounts.eve), 0);
+            assert_eq!(erc20.confirmations_of(action_hash), vec![accounts.bob]);
+
+            set_sender(accounts.django);
+            assert_eq!(erc20.confirm_action(action_hash), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.eve), 50);
+            assert_eq!(erc20.total_supply(), 150);
+            assert_eq!(erc20.confirmations_of(action_hash), Vec::<AccountId>::new());
+
+            // An executed action is gone.
+            set_sender(accounts.charlie);
+            assert_eq!(erc20.confirm_action(action_hash), Err(Error::UnknownAction));
+        }
+
+        #[ink::test]
+        fn set_multisig_rejects_invalid_threshold() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.set_multisig(vec![accounts.bob], 0), Err(Error::InvalidThreshold));
+            // Duplicates count once.
+            assert_eq!(
+                erc20.set_multisig(vec![accounts.bob, accounts.bob], 2),
+                Err(Error::InvalidThreshold)
+            );
+            assert_eq!(erc20.set_multisig(vec![accounts.bob], 1), Ok(()));
+            assert_eq!(erc20.set_multisig(vec![accounts.charlie], 1), Err(Error::MultisigRequired));
+
+            // With a threshold of one the proposal executes right away.
+            set_sender(accounts.bob);
+            assert!(erc20.propose_action(AdminAction::Pause).is_ok());
+            assert!(erc20.is_paused());
+        }
+
+        #[ink::test]
+        fn transfer_from_with_permit_works() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let owner = permit_signer();
+            let deadline = u64::MAX;
+            assert_eq!(erc20.transfer(owner, 60), Ok(()));
+
+            let hash = erc20.permit_hash(owner, accounts.bob, 50, 0, deadline);
+            let signature = sign_permit(hash);
+
+            // A permit for another spender is rejected as a permit failure.
+            set_sender(accounts.charlie);
+            assert_eq!(
+                erc20.transfer_from_with_permit(owner, accounts.eve, 50, deadline, signature),
+                Err(Error::InvalidSignature)
+            );
+
+            set_sender(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from_with_permit(owner, accounts.eve, 50, deadline, signature),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.eve), 50);
+            assert_eq!(erc20.balance_of(owner), 10);
+            assert_eq!(erc20.allowance(owner, accounts.bob), 0);
+            assert_eq!(erc20.nonce_of(owner), 1);
+        }
+
+        #[ink::test]
+        fn max_wallet_amount_limits_recipient_balance() {
+            let mut erc20 = Erc20::new(1000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.set_max_wallet_amount(Some(100)), Ok(()));
+            assert_eq!(erc20.max_wallet_amount(), Some(100));
+
+            assert_eq!(erc20.transfer(accounts.bob, 90), Ok(()));
+            assert_eq!(erc20.transfer(accounts.bob, 11), Err(Error::MaxWalletExceeded));
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 100);
+
+            // The owner is exempt as a recipient.
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.alice, 50), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 950);
+
+            assert_eq!(erc20.set_max_wallet_amount(None), Err(Error::NotOwner));
+        }
+
+        #[ink::test]
+        fn dividends_are_proportional_to_balances() {
+            let contract = set_contract_account();
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.transfer(accounts.bob, 25), Ok(()));
+
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(1000);
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract, 1000);
+            assert_eq!(erc20.distribute(), Ok(()));
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(0);
+            assert_eq!(erc20.dividends_of(accounts.alice), 750);
+            assert_eq!(erc20.dividends_of(accounts.bob), 250);
+
+            // Tokens moved after the distribution do not take dividends along.
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 25), Ok(()));
+            assert_eq!(erc20.dividends_of(accounts.bob), 250);
+            assert_eq!(erc20.dividends_of(accounts.charlie), 0);
+
+            let native_before =
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.bob)
+                    .expect("encountered unknown account");
+            assert_eq!(erc20.claim_dividends(), Ok(()));
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.bob),
+                Ok(native_before + 250)
+            );
+            assert_eq!(erc20.dividends_of(accounts.bob), 0);
+            assert_eq!(erc20.withdrawn_dividends(accounts.bob), 250);
+
+            set_sender(accounts.alice);
+            assert_eq!(erc20.claim_dividends(), Ok(()));
+            assert_eq!(erc20.withdrawn_dividends(accounts.alice), 750);
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(contract),
+                Ok(0)
+            );
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let event = emitted_events.last().expect("encountered no event");
+            let decoded = <Event as scale::Decode>::decode(&mut &event.data[..])
+                .expect("encountered invalid contract event data buffer");
+            assert!(matches!(decoded, Event::DividendClaimed(DividendClaimed { amount: 750, .. })));
+        }
+
+        #[ink::test]
+        fn distribute_without_supply_fails() {
+            let mut erc20 = Erc20::new(0);
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(10);
+            assert_eq!(erc20.distribute(), Err(Error::NoSupply));
+        }
+
+        #[ink::test]
+        fn time_weighted_balance_accumulates_holdings() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            mock::set_block_timestamp(1_000);
+            let mut erc20 = Erc20::new(100);
+
+            mock::set_block_timestamp(2_000);
+            assert_eq!(erc20.time_weighted_balance(accounts.alice, 1_000), 100 * 1_000);
+            assert_eq!(erc20.transfer(accounts.bob, 40), Ok(()));
+
+            mock::set_block_timestamp(5_000);
+            assert_eq!(erc20.time_weighted_balance(accounts.alice, 1_000), 100 * 1_000 + 60 * 3_000);
+            assert_eq!(erc20.time_weighted_balance(accounts.alice, 1_500), 100 * 500 + 60 * 3_000);
+            // Before its first balance change bob held nothing.
+            assert_eq!(erc20.time_weighted_balance(accounts.bob, 0), 40 * 3_000);
+            assert_eq!(erc20.time_weighted_balance(accounts.charlie, 0), 0);
+            assert_eq!(erc20.time_weighted_balance(accounts.alice, 5_000), 0);
+        }
+
+        #[ink::test]
+        fn time_weighted_balance_handles_many_checkpoints() {
+            let mut erc20 = Erc20::new(1_000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            // Dust at 600 distinct timestamps gives bob 600 checkpoints.
+            for i in 1..=600u64 {
+                mock::set_block_timestamp(i * 10);
+                assert_eq!(erc20.transfer(accounts.bob, 1), Ok(()));
+            }
+            mock::set_block_timestamp(7_000);
+
+            // Bob held i tokens from 10 * i until 10 * (i + 1).
+            let expected = |since: u64| -> u128 {
+                (1..=600u64)
+                    .map(|i| {
+                        let start = (i * 10).max(since);
+                        let end = if i == 600 { 7_000 } else { (i + 1) * 10 };
+                        (end.saturating_sub(start) as u128) * i as u128
+                    })
+                    .sum()
+            };
+            assert_eq!(erc20.time_weighted_balance(accounts.bob, 0), expected(0));
+            assert_eq!(erc20.time_weighted_balance(accounts.bob, 3_005), expected(3_005));
+
+            // The account keeps working.
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 600), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+        }
+
+        #[ink::test]
+        fn governance_executes_only_with_quorum_and_majority() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.transfer(accounts.bob, 30), Ok(()));
+            assert_eq!(erc20.transfer(accounts.charlie, 5), Ok(()));
+            for account in [accounts.alice, accounts.bob, accounts.charlie] {
+                set_sender(account);
+                erc20.delegate(account);
+            }
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+
+            let short_of_quorum = erc20.propose([1; 32], Vec::new());
+            let outvoted = erc20.propose([2; 32], Vec::new());
+            let passing = erc20.propose([3; 32], vec![0xde, 0xad]);
+            assert_eq!(erc20.proposal(passing).map(|proposal| proposal.quorum), Some(10));
+
+            // Charlie's 5 votes are below the quorum of 10.
+            assert_eq!(erc20.cast_vote(short_of_quorum, true), Ok(()));
+            assert_eq!(erc20.cast_vote(passing, false), Ok(()));
+            assert_eq!(erc20.cast_vote(passing, true), Err(Error::AlreadyVoted));
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.cast_vote(outvoted, true), Ok(()));
+            assert_eq!(erc20.cast_vote(passing, true), Ok(()));
+            set_sender(accounts.alice);
+            assert_eq!(erc20.cast_vote(outvoted, false), Ok(()));
+
+            // An account without votes at the proposal's start cannot vote.
+            set_sender(accounts.django);
+            assert_eq!(erc20.cast_vote(passing, true), Err(Error::NoVotingPower));
+
+            assert_eq!(erc20.execute(passing), Err(Error::VotingActive));
+            for _ in 0..=VOTING_PERIOD {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            }
+            assert_eq!(erc20.cast_vote(passing, true), Err(Error::VotingClosed));
+
+            assert_eq!(erc20.execute(short_of_quorum), Err(Error::QuorumNotReached));
+            assert_eq!(erc20.execute(outvoted), Err(Error::ProposalDefeated));
+            assert_eq!(erc20.execute(passing), Ok(()));
+            assert_eq!(erc20.execute(passing), Err(Error::ProposalAlreadyExecuted));
+            assert_eq!(erc20.execute(42), Err(Error::UnknownProposal));
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let event = emitted_events.last().expect("encountered no event");
+            let decoded = <Event as scale::Decode>::decode(&mut &event.data[..])
+                .expect("encountered invalid contract event data buffer");
+            assert!(matches!(
+                decoded,
+                Event::ProposalExecuted(ProposalExecuted { proposal_id, ref call_data })
+                    if proposal_id == passing && *call_data == vec![0xde, 0xad]
+            ));
+        }
+
+        #[ink::test]
+        fn votes_moved_in_the_creation_block_do_not_count_again() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            for account in [accounts.alice, accounts.bob] {
+                set_sender(account);
+                erc20.delegate(account);
+            }
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+
+            set_sender(accounts.alice);
+            let proposal_id = erc20.propose([1; 32], Vec::new());
+            assert_eq!(erc20.cast_vote(proposal_id, true), Ok(()));
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.get_votes(accounts.bob), 100);
+            assert_eq!(erc20.cast_vote(proposal_id, true), Err(Error::NoVotingPower));
+            assert_eq!(erc20.proposal(proposal_id).map(|proposal| proposal.for_votes), Some(100));
+        }
+
+        #[ink::test]
+        fn periodic_allowance_refreshes_every_period() {
+            let mut erc20 = Erc20::new(1000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            mock::set_block_timestamp(10_000);
+            assert_eq!(erc20.approve_periodic(accounts.bob, 100, 60), Ok(()));
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 70), Ok(()));
+            assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 31), Err(Error::PeriodLimitExceeded));
+            assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 30), Ok(()));
+            assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 1), Err(Error::PeriodLimitExceeded));
+            assert_eq!(
+                erc20.periodic_allowance(accounts.alice, accounts.bob),
+                Some((100, 60, 100, 10_000))
+            );
+
+            // One millisecond short of the period end nothing changes.
+            mock::set_block_timestamp(69_999);
+            assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 1), Err(Error::PeriodLimitExceeded));
+
+            mock::set_block_timestamp(70_000);
+            assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 200);
+            assert_eq!(
+                erc20.periodic_allowance(accounts.alice, accounts.bob),
+                Some((100, 60, 100, 70_000))
+            );
+
+            // A plain approval replaces the periodic one.
+            set_sender(accounts.alice);
+            assert_eq!(erc20.approve(accounts.bob, 5), Ok(()));
+            assert_eq!(erc20.periodic_allowance(accounts.alice, accounts.bob), None);
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 6), Err(Error::InsufficientApproval));
+        }
+
+        #[ink::test]
+        fn burn_from_counts_against_the_periodic_limit() {
+            let mut erc20 = Erc20::new(1000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            mock::set_block_timestamp(10_000);
+            assert_eq!(erc20.approve_periodic(accounts.bob, 100, 60), Ok(()));
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.burn_from(accounts.alice, 70), Ok(()));
+            assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 31), Err(Error::PeriodLimitExceeded));
+            assert_eq!(erc20.burn_from(accounts.alice, 31), Err(Error::PeriodLimitExceeded));
+            assert_eq!(erc20.burn_from(accounts.alice, 30), Ok(()));
+            assert_eq!(erc20.total_supply(), 900);
+            assert_eq!(
+                erc20.periodic_allowance(accounts.alice, accounts.bob),
+                Some((100, 60, 100, 10_000))
+            );
+
+            mock::set_block_timestamp(70_000);
+            assert_eq!(erc20.burn_from(accounts.alice, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 800);
+        }
+
+        #[ink::test]
+        fn periodic_allowance_cannot_be_increased_or_decreased() {
+            let mut erc20 = Erc20::new(1000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.approve_periodic(accounts.bob, 100, 60), Ok(()));
+
+            assert_eq!(erc20.increase_allowance(accounts.bob, 10), Err(Error::PeriodicAllowance));
+            assert_eq!(erc20.decrease_allowance(accounts.bob, 10), Err(Error::PeriodicAllowance));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 100);
+
+            // Once a plain approval replaced it, both work again.
+            assert_eq!(erc20.approve(accounts.bob, 50), Ok(()));
+            assert_eq!(erc20.increase_allowance(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.decrease_allowance(accounts.bob, 20), Ok(()));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 40);
+        }
+
+        #[ink::test]
+        fn terminate_is_guarded() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.terminate(accounts.bob, true), Err(Error::NotOwner));
+
+            set_sender(accounts.alice);
+            assert_eq!(erc20.terminate(accounts.alice, false), Err(Error::SupplyOutstanding));
+            assert_eq!(erc20.total_supply(), 100);
+        }
+
+        #[ink::test]
+        fn rounding_mode_applies_to_bps_shares() {
+            let mut erc20 = Erc20::new(1000);
+            assert_eq!(erc20.rounding_mode(), RoundingMode::Down);
+
+            for (mode, expected) in [
+                (RoundingMode::Down, [1, 0, 0, 16]),
+                (RoundingMode::Up, [2, 1, 1, 17]),
+                (RoundingMode::Nearest, [2, 1, 1, 17]),
+            ] {
+                assert_eq!(erc20.set_rounding_mode(mode), Ok(()));
+                // 1.665, 0.999, 0.5 and 16.65 units.
+                assert_eq!(
+                    [
+                        erc20.apply_bps(333, 50),
+                        erc20.apply_bps(333, 30),
+                        erc20.apply_bps(100, 50),
+                        erc20.apply_bps(3_330, 50),
+                    ],
+                    expected
+                );
+            }
+            assert_eq!(erc20.apply_bps(101, 50), 1);
+            assert_eq!(erc20.apply_bps(Balance::MAX, 10_000), Balance::MAX);
+        }
+
+        #[ink::test]
+        fn rounding_up_never_takes_more_than_the_value() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut erc20 = Erc20::new_with_fee(100, 1, accounts.eve);
+            assert_eq!(erc20.set_burn_bps(1), Ok(()));
+            assert_eq!(erc20.set_rounding_mode(RoundingMode::Up), Ok(()));
+
+            // The fee rounds up to the whole unit, leaving nothing to burn.
+            assert_eq!(erc20.transfer(accounts.bob, 1), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.balance_of(accounts.eve), 1);
+            assert_eq!(erc20.total_supply(), 100);
+        }
+
+        #[ink::test]
+        fn top_holders_sorts_by_balance() {
+            let mut erc20 = Erc20::new(1000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            for (account, value) in [
+                (accounts.bob, 100),
+                (accounts.charlie, 300),
+                (accounts.django, 50),
+                (accounts.eve, 200),
+            ] {
+                assert_eq!(erc20.transfer(account, value), Ok(()));
+            }
+
+            assert_eq!(
+                erc20.top_holders(3),
+                Ok(vec![(accounts.alice, 350), (accounts.charlie, 300), (accounts.eve, 200)])
+            );
+            assert_eq!(erc20.top_holders(10).map(|holders| holders.len()), Ok(5));
+            assert_eq!(erc20.top_holders(0), Ok(Vec::new()));
+            assert_eq!(
+                erc20.top_holders(MAX_EXPORT_ENTRIES as u32 + 1),
+                Err(Error::TooManyEntries)
+            );
+        }
+
+        #[ink::test]
+        fn lock_burns_and_emits_event() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.lock(40, 2, vec![0xab; 20]), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 60);
+            assert_eq!(erc20.total_supply(), 60);
+            assert_eq!(erc20.lock(61, 2, vec![0xab; 20]), Err(Error::InsufficientBalance));
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let event = emitted_events.last().expect("encountered no event");
+            let decoded = <Event as scale::Decode>::decode(&mut &event.data[..])
+                .expect("encountered invalid contract event data buffer");
+            if let Event::Locked(Locked { from, amount, dest_chain, dest_account }) = decoded {
+                assert_eq!(from, accounts.alice);
+                assert_eq!(amount, 40);
+                assert_eq!(dest_chain, 2);
+                assert_eq!(dest_account, vec![0xab; 20]);
+            } else {
+                panic!("encountered unexpected event kind: expected a Locked event")
+            }
+        }
+
+        #[ink::test]
+        fn unlock_rejects_replayed_nonce() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(erc20.unlock(accounts.bob, 10, 7), Ok(()));
+            assert_eq!(erc20.unlock(accounts.bob, 10, 7), Err(Error::NonceAlreadyUsed));
+            assert_eq!(erc20.balance_of(accounts.bob), 10);
+            assert_eq!(erc20.total_supply(), 110);
+
+            set_sender(accounts.charlie);
+            assert_eq!(erc20.unlock(accounts.charlie, 10, 8), Err(Error::MissingRole));
+        }
+
+        #[ink::test]
+        fn approval_history_keeps_most_recent_changes() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            for value in 1..=(APPROVAL_HISTORY_LEN as Balance + 2) {
+                mock::set_block_timestamp(value as u64);
+                assert_eq!(erc20.approve(accounts.bob, value), Ok(()));
+            }
+            let history = erc20.approval_history_of(accounts.alice);
+            assert_eq!(history.len(), APPROVAL_HISTORY_LEN);
+            assert_eq!(history[0], (accounts.bob, 3, 3));
+            assert_eq!(
+                history.last(),
+                Some(&(accounts.bob, APPROVAL_HISTORY_LEN as Balance + 2, APPROVAL_HISTORY_LEN as u64 + 2))
+            );
+
+            assert_eq!(erc20.revoke_allowance(accounts.bob), Ok(()));
+            let history = erc20.approval_history_of(accounts.alice);
+            assert_eq!(history[0], (accounts.bob, 4, 4));
+            assert_eq!(history.last(), Some(&(accounts.bob, 0, APPROVAL_HISTORY_LEN as u64 + 2)));
+            assert_eq!(erc20.approval_history_of(accounts.bob), Vec::new());
+        }
+
+        #[ink::test]
+        fn new_with_preapproval_sets_allowance() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let erc20 = Erc20::new_with_preapproval(100, accounts.bob, 40);
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 40);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 2);
+            assert_transfer_event(&emitted_events[0], None, Some(accounts.alice), 100);
+            let approval = decode_approval(&emitted_events[1]);
+            assert_eq!(approval.owner, accounts.alice);
+            assert_eq!(approval.spender, accounts.bob);
+            assert_eq!(approval.value, 40);
+        }
+
+        #[ink::test]
+        fn supply_invariant_holds_through_mixed_operations() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut erc20 = Erc20::new_with_fee(1000, 150, accounts.eve);
+            assert!(erc20.verify_supply_invariant());
+            assert_eq!(erc20.set_burn_bps(70), Ok(()));
+
+            assert_eq!(erc20.transfer(accounts.bob, 333), Ok(()));
+            assert!(erc20.verify_supply_invariant());
+            assert_eq!(erc20.mint(accounts.charlie, 77), Ok(()));
+            assert!(erc20.verify_supply_invariant());
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.burn(100), Ok(()));
+            assert!(erc20.verify_supply_invariant());
+            assert_eq!(erc20.stake(50), Ok(()));
+            let rest = erc20.balance_of(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, rest), Ok(()));
+            assert!(erc20.verify_supply_invariant());
+            assert_supply_accounting(&erc20);
+        }
+
+        #[ink::test]
+        fn buy_mints_at_fixed_price_and_refunds_remainder() {
+            let mut erc20 = Erc20::new_capped(0, 40);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let contract = ink_env::test::callee::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.buy(), Err(Error::SaleInactive));
+            assert_eq!(erc20.set_sale(3, true), Ok(()));
+
+            set_sender(accounts.bob);
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(100);
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract, 100);
+            let native_before =
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.bob)
+                    .expect("encountered unknown account");
+            assert_eq!(erc20.buy(), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 33);
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.bob),
+                Ok(native_before + 1)
+            );
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(contract),
+                Ok(99)
+            );
+
+            // Another 33 tokens would exceed the cap of 40.
+            assert_eq!(erc20.buy(), Err(Error::CapExceeded));
+            assert_eq!(erc20.total_supply(), 33);
+
+            set_sender(accounts.alice);
+            assert_eq!(erc20.set_sale(3, false), Ok(()));
+            set_sender(accounts.bob);
+            assert_eq!(erc20.buy(), Err(Error::SaleInactive));
+        }
+
+        #[ink::test]
+        fn burning_more_than_the_supply_fails() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            // Craft a balance the supply does not account for.
+            erc20.balances.insert(accounts.bob, &500);
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.burn(200), Err(Error::Overflow));
+            assert_eq!(erc20.total_supply(), 100);
+            assert_eq!(erc20.balance_of(accounts.bob), 500);
+            assert_eq!(erc20.total_burned(), 0);
+
+            assert_eq!(erc20.burn(100), Ok(()));
+            assert_eq!(erc20.total_supply(), 0);
+        }
+
+        #[ink::test]
+        fn failed_transfer_emits_event_only_when_enabled() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut erc20 = Erc20::new(100);
+            assert_eq!(erc20.transfer(accounts.bob, 101), Err(Error::InsufficientBalance));
+            assert_eq!(ink_env::test::recorded_events().count(), 1);
+
+            let mut erc20 = Erc20::new_with_failure_events(100);
+            assert_eq!(erc20.transfer(accounts.bob, 101), Err(Error::InsufficientBalance));
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 3);
+            let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[2].data[..])
+                .expect("encountered invalid contract event data buffer");
+            if let Event::TransferFailed(TransferFailed { from, to, value, reason }) = decoded {
+                assert_eq!((from, to, value), (accounts.alice, accounts.bob, 101));
+                assert_eq!(reason, scale::Encode::encode(&Error::InsufficientBalance)[0]);
+            } else {
+                panic!("encountered unexpected event kind: expected a TransferFailed event")
+            }
+        }
+
+        #[ink::test]
+        fn batched_reads_match_single_getters() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.transfer(accounts.bob, 30), Ok(()));
+            assert_eq!(erc20.approve(accounts.charlie, 5), Ok(()));
+            assert_eq!(erc20.approve(accounts.django, 7), Ok(()));
+
+            let holders = vec![accounts.eve, accounts.bob, accounts.alice, accounts.bob];
+            assert_eq!(erc20.balances_of(holders.clone()), Ok(vec![0, 30, 70, 30]));
+            assert_eq!(
+                erc20.balances_of(holders.clone()),
+                Ok(holders.iter().map(|holder| erc20.balance_of(*holder)).collect())
+            );
+
+            let spenders = vec![accounts.django, accounts.bob, accounts.charlie];
+            assert_eq!(erc20.allowances_of(accounts.alice, spenders), Ok(vec![7, 0, 5]));
+
+            assert_eq!(
+                erc20.balances_of(vec![accounts.bob; MAX_BATCH_LEN + 1]),
+                Err(Error::BatchTooLarge)
+            );
+            assert_eq!(
+                erc20.allowances_of(accounts.alice, vec![accounts.bob; MAX_BATCH_LEN + 1]),
+                Err(Error::BatchTooLarge)
+            );
+        }
+
+        #[ink::test]
+        fn auto_delegation_grants_votes_on_first_receipt() {
+            let mut erc20 = Erc20::new_with_auto_delegation(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.get_votes(accounts.alice), 100);
+
+            assert_eq!(erc20.transfer(accounts.bob, 30), Ok(()));
+            assert_eq!(erc20.delegates(accounts.bob), accounts.bob);
+            assert_eq!(erc20.get_votes(accounts.bob), 30);
+            assert_eq!(erc20.get_votes(accounts.alice), 70);
+
+            // An explicit choice made before the first receipt is kept.
+            set_sender(accounts.charlie);
+            erc20.delegate(accounts.django);
+            set_sender(accounts.alice);
+            assert_eq!(erc20.transfer(accounts.charlie, 20), Ok(()));
+            assert_eq!(erc20.get_votes(accounts.charlie), 0);
+            assert_eq!(erc20.get_votes(accounts.django), 20);
+
+            // Later delegation moves the votes away from oneself.
+            set_sender(accounts.bob);
+            erc20.delegate(accounts.django);
+            assert_eq!(erc20.get_votes(accounts.bob), 0);
+            assert_eq!(erc20.get_votes(accounts.django), 50);
+        }
+
+        #[ink::test]
+        fn without_auto_delegation_votes_need_delegate() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.transfer(accounts.bob, 30), Ok(()));
+            assert_eq!(erc20.get_votes(accounts.alice), 0);
+            assert_eq!(erc20.get_votes(accounts.bob), 0);
+
+            set_sender(accounts.bob);
+            erc20.delegate(accounts.bob);
+            assert_eq!(erc20.get_votes(accounts.bob), 30);
+        }
+
+        #[ink::test]
+        fn migrate_balances_skips_present_accounts() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+            assert_eq!(
+                erc20.migrate_balances(vec![(accounts.bob, 10), (accounts.charlie, 20), (accounts.bob, 99)]),
+                Ok(())
+            );
+            // The second page overlaps the first and the deployer's balance.
+            assert_eq!(
+                erc20.migrate_balances(vec![(accounts.charlie, 20), (accounts.alice, 5), (accounts.django, 30)]),
+                Ok(())
+            );
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+            assert_eq!(erc20.balance_of(accounts.bob), 10);
+            assert_eq!(erc20.balance_of(accounts.charlie), 20);
+            assert_eq!(erc20.balance_of(accounts.django), 30);
+            assert_eq!(erc20.total_supply(), 160);
+            assert!(erc20.verify_supply_invariant());
+
+            assert_eq!(erc20.finish_migration(), Ok(()));
+            assert!(erc20.migration_complete());
+            assert_eq!(erc20.migrate_balances(vec![(accounts.eve, 1)]), Err(Error::MigrationComplete));
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.migrate_balances(Vec::new()), Err(Error::NotOwner));
         }
 
-        /// The default constructor does its job.
         #[ink::test]
-        fn new_works() {
-            // Constructor works.
-            let _erc20 = Erc20::new(100);
+        fn prelaunch_restricts_transfers_to_whitelist() {
+            let mut erc20 = Erc20::new_prelaunch(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert!(!erc20.trading_enabled());
 
-            // Transfer event triggered during initial construction.
-            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_eq!(1, emitted_events.len());
+            // The whitelisted deployer distributes the supply.
+            assert_eq!(erc20.transfer(accounts.bob, 40), Ok(()));
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 10), Err(Error::TradingNotEnabled));
+            assert_eq!(erc20.transfer(accounts.alice, 10), Ok(()));
 
-            assert_transfer_event(
-                &emitted_events[0],
-                None,
-                Some(AccountId::from([0x01; 32])),
-                100,
-            );
+            set_sender(accounts.alice);
+            assert_eq!(erc20.set_whitelisted(accounts.charlie, true), Ok(()));
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 10), Ok(()));
+
+            set_sender(accounts.alice);
+            assert_eq!(erc20.enable_trading(), Ok(()));
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.django, 10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.django), 10);
+            assert_eq!(erc20.enable_trading(), Err(Error::NotOwner));
         }
 
-        /// The total supply was applied.
         #[ink::test]
-        fn total_supply_works() {
-            // Constructor works.
-            let erc20 = Erc20::new(100);
-            // Transfer event triggered during initial construction.
+        fn failed_withdrawal_is_escrowed_until_claimed() {
+            let contract = set_contract_account();
+            let mut erc20 = Erc20::new(0);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(100);
+            assert_eq!(erc20.deposit(), Ok(()));
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(0);
+
+            // With nothing in the contract the payout fails.
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract, 0);
+            assert_eq!(erc20.withdraw(40), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.alice), 60);
+            assert_eq!(erc20.pending_withdrawal(accounts.alice), 40);
+
             let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_transfer_event(
-                &emitted_events[0],
-                None,
-                Some(AccountId::from([0x01; 32])),
-                100,
+            let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[3].data[..])
+                .expect("encountered invalid contract event data buffer");
+            assert!(matches!(decoded, Event::WithdrawalEscrowed(WithdrawalEscrowed { amount: 40, .. })));
+
+            assert_eq!(erc20.claim_native(), Err(Error::NativeTransferFailed));
+
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract, 40);
+            let native_before =
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.alice)
+                    .expect("encountered unknown account");
+            assert_eq!(erc20.claim_native(), Ok(()));
+            assert_eq!(erc20.pending_withdrawal(accounts.alice), 0);
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.alice),
+                Ok(native_before + 40)
             );
-            // Get the token total supply.
-            assert_eq!(erc20.total_supply(), 100);
         }
 
-        /// Get the actual balance of an account.
         #[ink::test]
-        fn balance_of_works() {
-            // Constructor works
-            let erc20 = Erc20::new(100);
-            // Transfer event triggered during initial construction
-            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_transfer_event(
-                &emitted_events[0],
-                None,
-                Some(AccountId::from([0x01; 32])),
-                100,
+        fn config_reports_current_parameters() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut erc20 = Erc20::new_with_fee(100, 25, accounts.eve);
+            assert_eq!(erc20.set_burn_bps(10), Ok(()));
+            assert_eq!(erc20.set_max_tx_amount(Some(50)), Ok(()));
+            assert_eq!(erc20.set_max_wallet_amount(Some(80)), Ok(()));
+            assert_eq!(erc20.set_transfer_cooldown(1_000), Ok(()));
+            assert_eq!(erc20.set_rounding_mode(RoundingMode::Nearest), Ok(()));
+            assert_eq!(erc20.set_min_supply(20), Ok(()));
+            assert_eq!(erc20.set_mint_cap_per_block(Some(30)), Ok(()));
+            assert_eq!(erc20.set_oracle(Some(accounts.frank)), Ok(()));
+            assert_eq!(erc20.set_launch_block(Some(7)), Ok(()));
+            assert_eq!(erc20.pause(), Ok(()));
+
+            assert_eq!(
+                erc20.config(),
+                TokenConfig {
+                    cap: None,
+                    min_supply: Some(20),
+                    mint_cap_per_block: Some(30),
+                    max_tx_amount: Some(50),
+                    max_wallet_amount: Some(80),
+                    transfer_cooldown: 1_000,
+                    large_transfer_threshold: None,
+                    fee_bps: 25,
+                    burn_bps: 10,
+                    flash_fee_bps: 0,
+                    rounding_mode: RoundingMode::Nearest,
+                    treasury: Some(accounts.eve),
+                    oracle: Some(accounts.frank),
+                    paused: true,
+                    trading_enabled: true,
+                    launch_block: Some(7),
+                }
             );
+        }
+
+        #[ink::test]
+        fn metadata_setters_respect_freezing() {
             let accounts =
-                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
-                    .expect("Cannot get accounts");
-            // Alice owns all the tokens on deployment
-            assert_eq!(erc20.balance_of(accounts.alice), 100);
-            // Bob does not owns tokens
-            assert_eq!(erc20.balance_of(accounts.bob), 0);
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut erc20 = Erc20::new_mutable(100, Some(String::from("Old")), Some(String::from("OLD")), 6);
+            assert!(!erc20.metadata_frozen());
+            assert_eq!(erc20.set_name(Some(String::from("New"))), Ok(()));
+            assert_eq!(erc20.set_symbol(Some(String::from("NEW"))), Ok(()));
+            assert_eq!(erc20.token_name(), Some(String::from("New")));
+            assert_eq!(erc20.token_symbol(), Some(String::from("NEW")));
+
+            set_sender(accounts.bob);
+            assert_eq!(erc20.set_name(None), Err(Error::NotOwner));
+            assert_eq!(erc20.freeze_metadata(), Err(Error::NotOwner));
+
+            set_sender(accounts.alice);
+            assert_eq!(erc20.freeze_metadata(), Ok(()));
+            assert_eq!(erc20.set_name(None), Err(Error::MetadataFrozen));
+            assert_eq!(erc20.token_name(), Some(String::from("New")));
+
+            let mut erc20 = Erc20::new_immutable(100, Some(String::from("Fixed")), None, 18);
+            assert!(erc20.metadata_frozen());
+            assert_eq!(erc20.set_name(None), Err(Error::MetadataFrozen));
+            assert_eq!(erc20.set_symbol(Some(String::from("FIX"))), Err(Error::MetadataFrozen));
+            assert_eq!(erc20.token_decimals(), 18);
         }
 
         #[ink::test]
-        fn transfer_works() {
-            // Constructor works.
-            let mut erc20 = Erc20::new(100);
-            // Transfer event triggered during initial construction.
+        fn burns_stop_at_min_supply() {
+            let mut erc20 = Erc20::new_with_min_supply(100, 60);
             let accounts =
-                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
-                    .expect("Cannot get accounts");
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.min_supply(), Some(60));
 
-            assert_eq!(erc20.balance_of(accounts.bob), 0);
-            // Alice transfers 10 tokens to Bob.
-            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
-            // Bob owns 10 tokens.
-            assert_eq!(erc20.balance_of(accounts.bob), 10);
+            assert_eq!(erc20.burn(40), Ok(()));
+            assert_eq!(erc20.total_supply(), 60);
+            assert_eq!(erc20.burn(1), Err(Error::MinSupplyBreached));
+            assert_eq!(erc20.total_supply(), 60);
 
-            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_eq!(emitted_events.len(), 2);
-            // Check first transfer event related to ERC-20 instantiation.
-            assert_transfer_event(
-                &emitted_events[0],
-                None,
-                Some(AccountId::from([0x01; 32])),
-                100,
-            );
-            // Check the second transfer event relating to the actual trasfer.
-            assert_transfer_event(
-                &emitted_events[1],
-                Some(AccountId::from([0x01; 32])),
-                Some(AccountId::from([0x02; 32])),
-                10,
-            );
+            assert_eq!(erc20.set_min_supply(59), Err(Error::MinSupplyLowered));
+            assert_eq!(erc20.set_min_supply(70), Ok(()));
+            assert_eq!(erc20.mint(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.burn(1), Err(Error::MinSupplyBreached));
         }
 
         #[ink::test]
-        fn invalid_transfer_should_fail() {
-            // Constructor works.
-            let mut erc20 = Erc20::new(100);
+        fn transfer_burn_respects_min_supply() {
+            let mut erc20 = Erc20::new_with_min_supply(100, 100);
             let accounts =
-                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
-                    .expect("Cannot get accounts");
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.set_burn_bps(1_000), Ok(()));
 
+            assert_eq!(erc20.transfer(accounts.bob, 10), Err(Error::MinSupplyBreached));
             assert_eq!(erc20.balance_of(accounts.bob), 0);
-            // Get contract address.
-            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
-                .unwrap_or_else(|_| [0x0; 32].into());
-            // Create call
-            let mut data =
-                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])); // balance_of
-            data.push_arg(&accounts.bob);
-            // Push the new execution context to set Bob as caller
-            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
-                accounts.bob,
-                callee,
-                1000000,
-                1000000,
-                data,
-            );
+        }
 
-            // Bob fails to transfers 10 tokens to Eve.
-            assert_eq!(
-                erc20.transfer(accounts.eve, 10),
-                Err(Error::InsufficientBalance)
-            );
-            // Alice owns all the tokens.
-            assert_eq!(erc20.balance_of(accounts.alice), 100);
-            assert_eq!(erc20.balance_of(accounts.bob), 0);
-            assert_eq!(erc20.balance_of(accounts.eve), 0);
+        #[ink::test]
+        fn oracle_sets_transfer_fee() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let oracle = AccountId::from([0x0f; 32]);
+            mock::set_oracle_response(oracle, 500);
+            let mut erc20 = Erc20::new_with_fee(1000, 100, accounts.eve);
+            assert_eq!(erc20.set_oracle(Some(oracle)), Ok(()));
+            assert_eq!(erc20.oracle(), Some(oracle));
 
-            // Transfer event triggered during initial construction.
-            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_eq!(emitted_events.len(), 1);
-            assert_transfer_event(
-                &emitted_events[0],
-                None,
-                Some(AccountId::from([0x01; 32])),
-                100,
-            );
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 95);
+            assert_eq!(erc20.balance_of(accounts.eve), 5);
+
+            // A fee that leaves no room for the burn is ignored.
+            assert_eq!(erc20.set_burn_bps(9_600), Ok(()));
+            assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.eve), 6);
         }
 
         #[ink::test]
-        fn transfer_from_works() {
-            // Constructor works.
-            let mut erc20 = Erc20::new(100);
-            // Transfer event triggered during initial construction.
+        fn batch_transfer_reads_the_oracle_once() {
             let accounts =
-                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
-                    .expect("Cannot get accounts");
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let oracle = AccountId::from([0x0f; 32]);
+            mock::set_oracle_response(oracle, 500);
+            let mut erc20 = Erc20::new_with_fee(1000, 100, accounts.eve);
+            assert_eq!(erc20.set_oracle(Some(oracle)), Ok(()));
 
-            // Bob fails to transfer tokens owned by Alice.
+            let calls = mock::oracle_calls();
             assert_eq!(
-                erc20.transfer_from(accounts.alice, accounts.eve, 10),
-                Err(Error::InsufficientApproval)
+                erc20.batch_transfer(vec![
+                    (accounts.bob, 100),
+                    (accounts.charlie, 100),
+                    (accounts.django, 100)
+                ]),
+                Ok(())
             );
-            // Alice approves Bob for token transfers on her behalf.
-            assert_eq!(erc20.approve(accounts.bob, 10), Ok(()));
+            assert_eq!(mock::oracle_calls(), calls + 1);
+            assert_eq!(erc20.balance_of(accounts.bob), 95);
+            assert_eq!(erc20.balance_of(accounts.django), 95);
+            assert_eq!(erc20.balance_of(accounts.eve), 15);
+        }
 
-            // The approve event takes place.
-            assert_eq!(ink_env::test::recorded_events().count(), 2);
+        #[ink::test]
+        fn failing_oracle_falls_back_to_stored_fee() {
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let mut erc20 = Erc20::new_with_fee(1000, 100, accounts.eve);
+            // Nothing answers at this account.
+            assert_eq!(erc20.set_oracle(Some(AccountId::from([0x0e; 32]))), Ok(()));
 
-            // Get contract address.
-            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
-                .unwrap_or_else(|_| [0x0; 32].into());
-            // Create call.
-            let mut data =
-                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])); // balance_of
-            data.push_arg(&accounts.bob);
-            // Push the new execution context to set Bob as caller.
-            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
-                accounts.bob,
-                callee,
-                1000000,
-                1000000,
-                data,
-            );
+            assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 99);
+            assert_eq!(erc20.balance_of(accounts.eve), 1);
 
-            // Bob transfers tokens from Alice to Eve.
-            assert_eq!(
-                erc20.transfer_from(accounts.alice, accounts.eve, 10),
-                Ok(())
-            );
-            // Eve owns tokens.
-            assert_eq!(erc20.balance_of(accounts.eve), 10);
+            set_sender(accounts.bob);
+            assert_eq!(erc20.set_oracle(None), Err(Error::NotOwner));
+        }
 
-            // Check all transfer events that happened during the previous calls:
-            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_eq!(emitted_events.len(), 5);
-            assert_transfer_event(
-                &emitted_events[0],
-                None,
-                Some(AccountId::from([0x01; 32])),
-                100,
+        #[ink::test]
+        fn withdraw_all_cannot_be_reentered_for_more() {
+            let contract = set_contract_account();
+            let mut erc20 = Erc20::new(0);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(100);
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract, 150);
+            assert_eq!(erc20.deposit(), Ok(()));
+            ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(0);
+
+            let native_before =
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.alice)
+                    .expect("encountered unknown account");
+            mock::set_reentrant_withdrawer(accounts.alice);
+            assert_eq!(erc20.withdraw_all(), Ok(()));
+
+            // The nested call found the balance already burned.
+            assert_eq!(mock::take_reentry(), Some(Ok(())));
+            assert_eq!(erc20.balance_of(accounts.alice), 0);
+            assert_eq!(erc20.total_supply(), 0);
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.alice),
+                Ok(native_before + 100)
             );
-            // The second event `emitted_events[1]` is an Approve event that we skip checking.
-            assert_transfer_event(
-                &emitted_events[2],
-                Some(AccountId::from([0x01; 32])),
-                Some(AccountId::from([0x05; 32])),
-                10,
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(contract),
+                Ok(50)
             );
         }
 
         #[ink::test]
-        fn allowance_must_not_change_on_failed_transfer() {
+        fn selector_table_matches_generated_selectors() {
+            assert_eq!(selectors::TRANSFER, ink::selector_bytes!("transfer"));
+            assert_eq!(selectors::APPROVE, ink::selector_bytes!("approve"));
+            assert_eq!(selectors::TRANSFER_FROM, ink::selector_bytes!("transfer_from"));
+            assert_eq!(selectors::PSP22_TRANSFER, ink::selector_bytes!("PSP22::transfer"));
+            assert_eq!(selectors::PSP22_APPROVE, ink::selector_bytes!("PSP22::approve"));
+        }
+
+        #[ink::test]
+        fn transfer_dispatches_by_fixed_selector() {
             let mut erc20 = Erc20::new(100);
             let accounts =
-                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
-                    .expect("Cannot get accounts");
-
-            // Alice approves Bob for token transfers on her behalf.
-            let alice_balance = erc20.balance_of(accounts.alice);
-            let initial_allowance = alice_balance + 2;
-            assert_eq!(erc20.approve(accounts.bob, initial_allowance), Ok(()));
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
 
-            // Get contract address.
-            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()
-                .unwrap_or_else(|_| [0x0; 32].into());
-            // Create call.
-            let mut data =
-                ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4])); // balance_of
-            data.push_arg(&accounts.bob);
-            // Push the new execution context to set Bob as caller.
-            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
-                accounts.bob,
-                callee,
-                1000000,
-                1000000,
-                data,
+            let mut input = selectors::TRANSFER.to_vec();
+            scale::Encode::encode_to(&(accounts.bob, 30 as Balance), &mut input);
+            assert!(
+                <<Erc20 as ink::reflect::ContractMessageDecoder>::Type as scale::Decode>::decode(
+                    &mut &input[..]
+                )
+                .is_ok()
             );
-
-            // Bob tries to transfer tokens from Alice to Eve.
-            let emitted_events_before = ink_env::test::recorded_events();
-            assert_eq!(
-                erc20.transfer_from(accounts.alice, accounts.eve, alice_balance + 1),
-                Err(Error::InsufficientBalance)
+            let mut unknown = [0xff; 4].to_vec();
+            unknown.extend_from_slice(&input[4..]);
+            assert!(
+                <<Erc20 as ink::reflect::ContractMessageDecoder>::Type as scale::Decode>::decode(
+                    &mut &unknown[..]
+                )
+                .is_err()
             );
-            // Allowance must have stayed the same
+
+            let args = <(AccountId, Balance) as scale::Decode>::decode(&mut &input[4..])
+                .expect("encountered invalid call arguments");
+            let transfer = <Erc20 as ink::reflect::DispatchableMessageInfo<
+                { u32::from_be_bytes(selectors::TRANSFER) },
+            >>::CALLABLE;
+            assert_eq!(transfer(&mut erc20, args), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 30);
+        }
+
+        #[ink::test]
+        fn mint_cap_per_block_resets_on_next_block() {
+            let mut erc20 = Erc20::new(0);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.set_mint_cap_per_block(Some(100)), Ok(()));
+            assert_eq!(erc20.mint_cap_per_block(), Some(100));
+
+            assert_eq!(erc20.mint(accounts.bob, 60), Ok(()));
             assert_eq!(
-                erc20.allowance(accounts.alice, accounts.bob),
-                initial_allowance
+                erc20.mint_batch(vec![(accounts.bob, 20), (accounts.charlie, 21)]),
+                Err(Error::MintRateExceeded)
             );
-            // No more events must have been emitted
-            let emitted_events_after = ink_env::test::recorded_events();
-            assert_eq!(emitted_events_before.count(), emitted_events_after.count());
+            assert_eq!(erc20.mint_batch(vec![(accounts.bob, 20), (accounts.charlie, 20)]), Ok(()));
+            assert_eq!(erc20.mint(accounts.bob, 1), Err(Error::MintRateExceeded));
+            assert_eq!(erc20.total_supply(), 100);
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.mint(accounts.bob, 100), Ok(()));
+            assert_eq!(erc20.mint(accounts.bob, 1), Err(Error::MintRateExceeded));
+            assert_eq!(erc20.total_supply(), 200);
         }
-    }
 
-    /// For calculating the event topic hash.
-    struct PrefixedValue<'a, 'b, T> {
-        pub prefix: &'a [u8],
-        pub value: &'b T,
-    }
+        #[ink::test]
+        fn multisig_mint_counts_against_mint_cap_per_block() {
+            let mut erc20 = Erc20::new(0);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.set_mint_cap_per_block(Some(100)), Ok(()));
+            assert_eq!(erc20.set_multisig(vec![accounts.bob], 1), Ok(()));
 
-    impl<X> scale::Encode for PrefixedValue<'_, '_, X>
-        where
-            X: scale::Encode,
-    {
-        #[inline]
-        fn size_hint(&self) -> usize {
-            self.prefix.size_hint() + self.value.size_hint()
+            set_sender(accounts.bob);
+            let mint = AdminAction::Mint { to: accounts.eve, value: 101 };
+            assert_eq!(erc20.propose_action(mint), Err(Error::MintRateExceeded));
+            assert_eq!(erc20.total_supply(), 0);
+            let mint = AdminAction::Mint { to: accounts.eve, value: 70 };
+            assert!(erc20.propose_action(mint).is_ok());
+            let mint = AdminAction::Mint { to: accounts.eve, value: 31 };
+            assert_eq!(erc20.propose_action(mint), Err(Error::MintRateExceeded));
+            assert_eq!(erc20.total_supply(), 70);
         }
 
-        #[inline]
-        fn encode_to<T: scale::Output + ?Sized>(&self, dest: &mut T) {
-            self.prefix.encode_to(dest);
-            self.value.encode_to(dest);
+        #[ink::test]
+        fn only_unlocked_tokens_are_transferable() {
+            let mut erc20 = Erc20::new(1000);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.create_vesting(accounts.bob, 400, 0, 4_000), Ok(()));
+            mock::set_block_timestamp(1_000);
+            set_sender(accounts.bob);
+            assert_eq!(erc20.release(), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 100);
+
+            // The other 300 are still vesting.
+            assert_eq!(
+                erc20.transfer(accounts.charlie, 101),
+                Err(Error::InsufficientUnlockedBalance)
+            );
+            assert_eq!(erc20.transfer(accounts.charlie, 401), Err(Error::InsufficientBalance));
+
+            // Staked tokens are locked as well.
+            assert_eq!(erc20.stake(40), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 60);
+            assert_eq!(
+                erc20.transfer(accounts.charlie, 61),
+                Err(Error::InsufficientUnlockedBalance)
+            );
+            assert_eq!(erc20.transfer(accounts.charlie, 60), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 60);
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
         }
-    }
 
-    #[cfg(feature = "ink-experimental-engine")]
-    #[cfg(test)]
-    mod tests_experimental_engine {
-        use super::*;
+        #[ink::test]
+        fn transfer_once_ignores_retries() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.transfer_once(accounts.bob, 10, 7), Ok(()));
+            assert_eq!(erc20.transfer_once(accounts.bob, 10, 7), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 10);
+            assert_eq!(erc20.balance_of(accounts.alice), 90);
 
-        use ink_env::Clear;
-        use ink_lang as ink;
+            // A failed attempt leaves the nonce free to retry.
+            assert_eq!(erc20.transfer_once(accounts.bob, 91, 8), Err(Error::InsufficientBalance));
+            assert_eq!(erc20.transfer_once(accounts.bob, 90, 8), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.bob), 100);
 
-        type Event = <Erc20 as ::ink_lang::BaseEvent>::Type;
+            // Nonces are per caller.
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer_once(accounts.charlie, 5, 7), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 5);
+        }
 
-        fn assert_transfer_event(
-            event: &ink_env::test::EmittedEvent,
-            expected_from: Option<AccountId>,
-            expected_to: Option<AccountId>,
-            expected_value: Balance,
-        ) {
-            let decoded_event = <Event as scale::Decode>::decode(&mut &event.data[..])
+        #[ink::test]
+        fn indexed_values_emit_value_topic() {
+            let mut erc20 = Erc20::new_with_indexed_values(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            // Mint and transfer, each as `Transfer` and `TransferIndexed`.
+            assert_eq!(emitted_events.len(), 4);
+            let event = &emitted_events[3];
+            let value: Balance = 10;
+            let decoded = <Event as scale::Decode>::decode(&mut &event.data[..])
                 .expect("encountered invalid contract event data buffer");
-            if let Event::Transfer(Transfer { from, to, value }) = decoded_event {
-                assert_eq!(from, expected_from, "encountered invalid Transfer.from");
-                assert_eq!(to, expected_to, "encountered invalid Transfer.to");
-                assert_eq!(value, expected_value, "encountered invalid Trasfer.value");
-            } else {
-                panic!("encountered unexpected event kind: expected a Transfer event")
-            }
-            let expected_topics = vec![
+            assert!(matches!(
+                decoded,
+                Event::TransferIndexed(TransferIndexed { value: 10, .. })
+            ));
+            assert_topics(event, vec![
                 encoded_into_hash(&PrefixedValue {
-                    value: b"Erc20::Transfer",
+                    value: b"Erc20::TransferIndexed",
                     prefix: b"",
                 }),
                 encoded_into_hash(&PrefixedValue {
-                    prefix: b"Erc20::Transfer::from",
-                    value: &expected_from,
+                    prefix: b"Erc20::TransferIndexed::from",
+                    value: &Some(accounts.alice),
                 }),
                 encoded_into_hash(&PrefixedValue {
-                    prefix: b"Erc20::Transfer::to",
-                    value: &expected_to,
+                    prefix: b"Erc20::TransferIndexed::to",
+                    value: &Some(accounts.bob),
                 }),
                 encoded_into_hash(&PrefixedValue {
-                    prefix: b"Erc20::Transfer::value",
-                    value: &expected_value,
+                    prefix: b"Erc20::TransferIndexed::value",
+                    value: &value,
                 }),
-            ];
-
-            let topics = event.topics.clone();
-            for (n, (actual_topic, expected_topic)) in
-            topics.iter().zip(expected_topics).enumerate()
-            {
-                let mut topic_hash = Hash::clear();
-                let len = actual_topic.len();
-                topic_hash.as_mut()[0..len].copy_from_slice(&actual_topic[0..len]);
+            ]);
 
-                assert_eq!(
-                    topic_hash, expected_topic,
-                    "encountered invalid topic at {}",
-                    n
-                );
-            }
+            // The default constructor keeps the old event set.
+            let mut erc20 = Erc20::new(100);
+            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!(ink_env::test::recorded_events().count(), 6);
         }
 
-        /// The default constructor does its job.
         #[ink::test]
-        fn new_works() {
-            // Constructor works.
-            let _erc20 = Erc20::new(100);
-
-            // Transfer event triggered during initial construction.
-            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_eq!(1, emitted_events.len());
+        fn total_allowance_granted_sums_spenders() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.total_allowance_granted(accounts.alice), 0);
+            assert_eq!(erc20.approve(accounts.bob, 30), Ok(()));
+            assert_eq!(erc20.approve(accounts.charlie, 50), Ok(()));
+            assert_eq!(erc20.total_allowance_granted(accounts.alice), 80);
 
-            assert_transfer_event(
-                &emitted_events[0],
-                None,
-                Some(AccountId::from([0x01; 32])),
-                100,
-            );
-        }
+            // Spending and revoking lower the total.
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 30), Ok(()));
+            set_sender(accounts.alice);
+            assert_eq!(erc20.total_allowance_granted(accounts.alice), 50);
+            assert_eq!(erc20.revoke_allowance(accounts.charlie), Ok(()));
+            assert_eq!(erc20.total_allowance_granted(accounts.alice), 0);
 
-        /// The total supply was applied.
-        #[ink::test]
-        fn total_supply_works() {
-            // Constructor works.
-            let erc20 = Erc20::new(100);
-            // Transfer event triggered during initial construction.
-            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_transfer_event(
-                &emitted_events[0],
-                None,
-                Some(AccountId::from([0x01; 32])),
-                100,
-            );
-            // Get the token total supply.
-            assert_eq!(erc20.total_supply(), 100);
+            assert_eq!(erc20.approve(accounts.bob, Balance::MAX), Ok(()));
+            assert_eq!(erc20.approve(accounts.charlie, 1), Ok(()));
+            assert_eq!(erc20.total_allowance_granted(accounts.alice), Balance::MAX);
         }
 
-        /// Get the actual balance of an account.
         #[ink::test]
-        fn balance_of_works() {
-            // Constructor works
-            let erc20 = Erc20::new(100);
-            // Transfer event triggered during initial construction
-            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_transfer_event(
-                &emitted_events[0],
-                None,
-                Some(AccountId::from([0x01; 32])),
-                100,
-            );
+        fn removing_a_spender_moves_the_last_into_its_slot() {
+            let mut erc20 = Erc20::new(100);
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
-            // Alice owns all the tokens on deployment
-            assert_eq!(erc20.balance_of(accounts.alice), 100);
-            // Bob does not owns tokens
-            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            assert_eq!(erc20.approve(accounts.bob, 10), Ok(()));
+            assert_eq!(erc20.approve(accounts.charlie, 20), Ok(()));
+            assert_eq!(erc20.approve(accounts.django, 30), Ok(()));
+
+            assert_eq!(erc20.revoke_allowance(accounts.bob), Ok(()));
+            assert_eq!(erc20.spender_count.get(accounts.alice), Some(2));
+            assert_eq!(erc20.spenders.get((accounts.alice, 0)), Some(accounts.django));
+            assert_eq!(erc20.spender_index.get((accounts.alice, accounts.django)), Some(0));
+            assert_eq!(erc20.total_allowance_granted(accounts.alice), 50);
+
+            // Approving zero drops the spender as well.
+            assert_eq!(erc20.approve(accounts.charlie, 0), Ok(()));
+            assert_eq!(erc20.approve(accounts.django, 0), Ok(()));
+            assert_eq!(erc20.spender_count.get(accounts.alice), None);
+            assert_eq!(erc20.total_allowance_granted(accounts.alice), 0);
         }
 
         #[ink::test]
-        fn transfer_works() {
-            // Constructor works.
+        fn emergency_withdraw_sends_native_value() {
             let mut erc20 = Erc20::new(100);
-            // Transfer event triggered during initial construction.
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let contract = ink_env::test::callee::<ink_env::DefaultEnvironment>();
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract, 50);
+            assert_eq!(erc20.pause(), Ok(()));
 
-            assert_eq!(erc20.balance_of(accounts.bob), 0);
-            // Alice transfers 10 tokens to Bob.
-            assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
-            // Bob owns 10 tokens.
-            assert_eq!(erc20.balance_of(accounts.bob), 10);
-
-            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_eq!(emitted_events.len(), 2);
-            // Check first transfer event related to ERC-20 instantiation.
-            assert_transfer_event(
-                &emitted_events[0],
-                None,
-                Some(AccountId::from([0x01; 32])),
-                100,
+            let native_before =
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.bob)
+                    .expect("encountered unknown account");
+            assert_eq!(
+                erc20.emergency_withdraw(None, accounts.bob, 51),
+                Err(Error::InsufficientContractBalance)
             );
-            // Check the second transfer event relating to the actual trasfer.
-            assert_transfer_event(
-                &emitted_events[1],
-                Some(AccountId::from([0x01; 32])),
-                Some(AccountId::from([0x02; 32])),
-                10,
+            assert_eq!(erc20.emergency_withdraw(None, accounts.bob, 50), Ok(()));
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.bob),
+                Ok(native_before + 50)
             );
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            let decoded = <Event as scale::Decode>::decode(&mut &emitted_events.last().unwrap().data[..])
+                .expect("encountered invalid contract event data buffer");
+            assert!(matches!(
+                decoded,
+                Event::EmergencyWithdrawal(EmergencyWithdrawal { token: None, amount: 50, .. })
+            ));
         }
 
         #[ink::test]
-        fn invalid_transfer_should_fail() {
-            // Constructor works.
+        fn emergency_withdraw_sends_foreign_tokens() {
             let mut erc20 = Erc20::new(100);
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            mock::set_token_response(accounts.django, true);
+            mock::set_token_balance(accounts.django, 30);
 
-            assert_eq!(erc20.balance_of(accounts.bob), 0);
-
-            // Set the contract as callee and Bob as caller.
-            let contract = ink_env::account_id::<ink_env::DefaultEnvironment>()?;
-            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(contract);
-            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
-
-            // Bob fails to transfers 10 tokens to Eve.
             assert_eq!(
-                erc20.transfer(accounts.eve, 10),
-                Err(Error::InsufficientBalance)
+                erc20.emergency_withdraw(Some(accounts.django), accounts.bob, 31),
+                Err(Error::InsufficientContractBalance)
             );
-            // Alice owns all the tokens.
-            assert_eq!(erc20.balance_of(accounts.alice), 100);
-            assert_eq!(erc20.balance_of(accounts.bob), 0);
-            assert_eq!(erc20.balance_of(accounts.eve), 0);
-
-            // Transfer event triggered during initial construction.
-            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_eq!(emitted_events.len(), 1);
-            assert_transfer_event(
-                &emitted_events[0],
-                None,
-                Some(AccountId::from([0x01; 32])),
-                100,
+            assert_eq!(erc20.emergency_withdraw(Some(accounts.django), accounts.bob, 30), Ok(()));
+            // An account that is not a token contract cannot report a balance.
+            assert_eq!(
+                erc20.emergency_withdraw(Some(accounts.frank), accounts.bob, 1),
+                Err(Error::RecoveryFailed)
             );
         }
 
         #[ink::test]
-        fn transfer_from_works() {
-            // Constructor works.
+        fn non_owner_cannot_emergency_withdraw() {
             let mut erc20 = Erc20::new(100);
-            // Transfer event triggered during initial construction.
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            let contract = ink_env::test::callee::<ink_env::DefaultEnvironment>();
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract, 50);
+            mock::set_token_response(accounts.django, true);
+            mock::set_token_balance(accounts.django, 30);
 
-            // Bob fails to transfer tokens owned by Alice.
+            set_sender(accounts.bob);
+            assert_eq!(erc20.emergency_withdraw(None, accounts.bob, 10), Err(Error::NotOwner));
             assert_eq!(
-                erc20.transfer_from(accounts.alice, accounts.eve, 10),
-                Err(Error::InsufficientAllowance)
+                erc20.emergency_withdraw(Some(accounts.django), accounts.bob, 10),
+                Err(Error::NotOwner)
             );
-            // Alice approves Bob for token transfers on her behalf.
-            assert_eq!(erc20.approve(accounts.bob, 10), Ok(()));
+        }
 
-            // The approve event takes place.
-            assert_eq!(ink_env::test::recorded_events().count(), 2);
+        #[ink::test]
+        fn transfers_wait_for_launch_block() {
+            let mut erc20 = Erc20::new(100);
+            let accounts =
+                ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.set_launch_block(Some(2)), Ok(()));
+            assert_eq!(erc20.launch_block(), Some(2));
 
-            // Set the contract as callee and Bob as caller.
-            let contract = ink_env::account_id::<ink_env::DefaultEnvironment>()?;
-            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(contract);
-            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            // The owner seeds liquidity before the launch.
+            assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 10), Err(Error::NotLaunchedYet));
+            assert_eq!(erc20.set_launch_block(None), Err(Error::NotOwner));
 
-            // Bob transfers tokens from Alice to Eve.
-            assert_eq!(
-                erc20.transfer_from(accounts.alice, accounts.eve, 10),
-                Ok(())
-            );
-            // Eve owns tokens.
-            assert_eq!(erc20.balance_of(accounts.eve), 10);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.transfer(accounts.charlie, 10), Err(Error::NotLaunchedYet));
 
-            // Check all transfer events that happened during the previous calls:
-            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_eq!(emitted_events.len(), 3);
-            assert_transfer_event(
-                &emitted_events[0],
-                None,
-                Some(AccountId::from([0x01; 32])),
-                100,
-            );
-            // The second event `emitted_events[1]` is an Approve event that we skip checking.
-            assert_transfer_event(
-                &emitted_events[2],
-                Some(AccountId::from([0x01; 32])),
-                Some(AccountId::from([0x05; 32])),
-                10,
-            );
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+            assert_eq!(erc20.transfer(accounts.charlie, 10), Ok(()));
+            assert_eq!(erc20.balance_of(accounts.charlie), 10);
+
+            set_sender(accounts.alice);
+            assert_eq!(erc20.set_launch_block(Some(10)), Err(Error::AlreadyLaunched));
         }
 
         #[ink::test]
-        fn allowance_must_not_change_on_failed_transfer() {
+        fn launch_block_cannot_halt_a_live_token() {
             let mut erc20 = Erc20::new(100);
             let accounts =
                 ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+            // Owner transfers alone do not start trading.
+            assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+            assert_eq!(erc20.set_launch_block(Some(5)), Ok(()));
+            assert_eq!(erc20.set_launch_block(None), Ok(()));
 
-            // Alice approves Bob for token transfers on her behalf.
-            let alice_balance = erc20.balance_of(accounts.alice);
-            let initial_allowance = alice_balance + 2;
-            assert_eq!(erc20.approve(accounts.bob, initial_allowance), Ok(()));
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 10), Ok(()));
+            set_sender(accounts.alice);
+            assert_eq!(erc20.set_launch_block(Some(5)), Err(Error::AlreadyLaunched));
+            assert_eq!(erc20.launch_block(), None);
 
-            // Get contract address.
-            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>()?;
-            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(callee);
-            ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+            set_sender(accounts.bob);
+            assert_eq!(erc20.transfer(accounts.charlie, 10), Ok(()));
+        }
+    }
 
-            // Bob tries to transfer tokens from Alice to Eve.
-            let emitted_events_before =
-                ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_eq!(
-                erc20.transfer_from(accounts.alice, accounts.eve, alice_balance + 1),
-                Err(Error::InsufficientBalance)
-            );
-            // Allowance must have stayed the same
-            assert_eq!(
-                erc20.allowance(accounts.alice, accounts.bob),
-                initial_allowance
-            );
-            // No more events must have been emitted
-            let emitted_events_after =
-                ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_eq!(emitted_events_before.len(), emitted_events_after.len());
+    /// For calculating the event topic hash.
+    #[cfg(test)]
+    struct PrefixedValue<'a, 'b, T> {
+        pub prefix: &'a [u8],
+        pub value: &'b T,
+    }
+
+    #[cfg(test)]
+    impl<X> scale::Encode for PrefixedValue<'_, '_, X>
+        where
+            X: scale::Encode,
+    {
+        #[inline]
+        fn size_hint(&self) -> usize {
+            self.prefix.size_hint() + self.value.size_hint()
+        }
+
+        #[inline]
+        fn encode_to<T: scale::Output + ?Sized>(&self, dest: &mut T) {
+            self.prefix.encode_to(dest);
+            self.value.encode_to(dest);
         }
     }
 
@@ -795,4 +8834,141 @@ mod erc20 {
         result
     }
 
+    /// Stand-ins for cross-contract calls, which the off-chain environment
+    /// cannot execute.
+    #[cfg(test)]
+    mod mock {
+        use super::*;
+        use core::cell::RefCell;
+        use std::collections::HashMap;
+
+        thread_local! {
+            static RECEIVER_RESPONSES: RefCell<HashMap<AccountId, Option<[u8; 4]>>> =
+                RefCell::new(HashMap::new());
+            static BORROWER_RESPONSES: RefCell<HashMap<AccountId, Option<[u8; 4]>>> =
+                RefCell::new(HashMap::new());
+            static TOKEN_RESPONSES: RefCell<HashMap<AccountId, bool>> =
+                RefCell::new(HashMap::new());
+            static TOKEN_BALANCES: RefCell<HashMap<AccountId, Balance>> =
+                RefCell::new(HashMap::new());
+            static ORACLE_RESPONSES: RefCell<HashMap<AccountId, u16>> =
+                RefCell::new(HashMap::new());
+            static ORACLE_CALLS: RefCell<u32> = const { RefCell::new(0) };
+            static BLOCK_TIMESTAMP: RefCell<u64> = const { RefCell::new(0) };
+            static REENTRANT_RECEIVERS: RefCell<Vec<AccountId>> = const { RefCell::new(Vec::new()) };
+            static REENTRANT_WITHDRAWERS: RefCell<Vec<AccountId>> = const { RefCell::new(Vec::new()) };
+            static REENTRY_RESULT: RefCell<Option<Result<()>>> = const { RefCell::new(None) };
+        }
+
+        /// Turns `receiver` into a `TokenReceiver` contract answering with
+        /// `response`, or trapping if it is `None`.
+        pub fn set_receiver_response(receiver: AccountId, response: Option<[u8; 4]>) {
+            RECEIVER_RESPONSES.with(|responses| responses.borrow_mut().insert(receiver, response));
+        }
+
+        /// Returns `None` if `receiver` is not a registered contract.
+        pub fn receiver_response(receiver: AccountId) -> Option<Option<[u8; 4]>> {
+            RECEIVER_RESPONSES.with(|responses| responses.borrow().get(&receiver).copied())
+        }
+
+        /// Turns `borrower` into a `FlashBorrower` contract answering with
+        /// `response`, or trapping if it is `None`.
+        pub fn set_borrower_response(borrower: AccountId, response: Option<[u8; 4]>) {
+            BORROWER_RESPONSES.with(|responses| responses.borrow_mut().insert(borrower, response));
+        }
+
+        /// Returns `None` if `borrower` is not a registered contract.
+        pub fn borrower_response(borrower: AccountId) -> Option<Option<[u8; 4]>> {
+            BORROWER_RESPONSES.with(|responses| responses.borrow().get(&borrower).copied())
+        }
+
+        /// Turns `receiver` into an accepting `TokenReceiver` contract that
+        /// calls `transfer_and_call` again from within its callback.
+        pub fn set_reentrant_receiver(receiver: AccountId) {
+            set_receiver_response(receiver, Some(ON_TOKEN_RECEIVED_SELECTOR));
+            REENTRANT_RECEIVERS.with(|receivers| receivers.borrow_mut().push(receiver));
+        }
+
+        pub fn is_reentrant_receiver(receiver: AccountId) -> bool {
+            REENTRANT_RECEIVERS.with(|receivers| receivers.borrow().contains(&receiver))
+        }
+
+        /// Makes the next native payout to `account` call `withdraw_all`
+        /// once more before it arrives.
+        pub fn set_reentrant_withdrawer(account: AccountId) {
+            REENTRANT_WITHDRAWERS.with(|withdrawers| withdrawers.borrow_mut().push(account));
+        }
+
+        /// Returns whether `account` re-enters on this payout, consuming the
+        /// registration.
+        pub fn take_reentrant_withdrawer(account: AccountId) -> bool {
+            REENTRANT_WITHDRAWERS.with(|withdrawers| {
+                let mut withdrawers = withdrawers.borrow_mut();
+                match withdrawers.iter().position(|withdrawer| *withdrawer == account) {
+                    Some(index) => {
+                        withdrawers.swap_remove(index);
+                        true
+                    }
+                    None => false,
+                }
+            })
+        }
+
+        pub fn record_reentry(result: Result<()>) {
+            REENTRY_RESULT.with(|reentry| *reentry.borrow_mut() = Some(result));
+        }
+
+        /// Returns the result of the latest re-entrant call, if any.
+        pub fn take_reentry() -> Option<Result<()>> {
+            REENTRY_RESULT.with(|reentry| reentry.borrow_mut().take())
+        }
+
+        /// Turns `token` into a PSP22 contract whose `transfer` succeeds if
+        /// `accepts` is set and fails otherwise.
+        pub fn set_token_response(token: AccountId, accepts: bool) {
+            TOKEN_RESPONSES.with(|responses| responses.borrow_mut().insert(token, accepts));
+        }
+
+        /// Returns `None` if `token` is not a registered contract.
+        pub fn token_response(token: AccountId) -> Option<bool> {
+            TOKEN_RESPONSES.with(|responses| responses.borrow().get(&token).copied())
+        }
+
+        /// Makes the PSP22 contract `token` report `balance` for this contract.
+        pub fn set_token_balance(token: AccountId, balance: Balance) {
+            TOKEN_BALANCES.with(|balances| balances.borrow_mut().insert(token, balance));
+        }
+
+        /// Returns `None` if `token` is not a registered contract.
+        pub fn token_balance(token: AccountId) -> Option<Balance> {
+            TOKEN_BALANCES.with(|balances| balances.borrow().get(&token).copied())
+        }
+
+        /// Turns `oracle` into a `FeeOracle` contract answering with `fee_bps`.
+        pub fn set_oracle_response(oracle: AccountId, fee_bps: u16) {
+            ORACLE_RESPONSES.with(|responses| responses.borrow_mut().insert(oracle, fee_bps));
+        }
+
+        /// Returns `None`, as a failed call would, if `oracle` is not a
+        /// registered contract.
+        pub fn oracle_response(oracle: AccountId) -> Option<u16> {
+            ORACLE_CALLS.with(|calls| *calls.borrow_mut() += 1);
+            ORACLE_RESPONSES.with(|responses| responses.borrow().get(&oracle).copied())
+        }
+
+        /// How many times an oracle has been queried on this thread.
+        pub fn oracle_calls() -> u32 {
+            ORACLE_CALLS.with(|calls| *calls.borrow())
+        }
+
+        /// Sets the timestamp the contract reads as the current block's.
+        pub fn set_block_timestamp(timestamp: u64) {
+            BLOCK_TIMESTAMP.with(|current| *current.borrow_mut() = timestamp);
+        }
+
+        pub fn block_timestamp() -> u64 {
+            BLOCK_TIMESTAMP.with(|current| *current.borrow())
+        }
+    }
+
 }